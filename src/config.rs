@@ -0,0 +1,116 @@
+//! Game configuration.
+
+use std::{fs, io, path::Path};
+
+use serde::Deserialize;
+
+use crate::{Gameboard, RsweeperError};
+
+/// The initial setup of the game, read from a TOML file.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameConfig {
+    /// The size of the gameboard.
+    pub size: [usize; 2],
+    /// The number of bombs in the game.
+    pub bombs: usize,
+    /// The seed of the bomb placement, random if absent.
+    pub seed: Option<u64>,
+    /// The color theme file, the default theme file if absent.
+    pub theme: Option<String>,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            size: [10, 10],
+            bombs: 10,
+            seed: None,
+            theme: None,
+        }
+    }
+}
+
+impl GameConfig {
+    /// Parses and validates a configuration.
+    pub fn parse(s: &str) -> Result<Self, RsweeperError> {
+        let config: Self = toml::from_str(s)
+            .map_err(|e| RsweeperError::Parse(e.to_string()))?;
+        config.build_board()?;
+        Ok(config)
+    }
+
+    /// Reads and validates the configuration file at `path`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, RsweeperError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Reads the configuration file at `path`, falling back to the default
+    /// configuration if it is absent or invalid.
+    pub fn load_or_default<P: AsRef<Path>>(path: P) -> Self {
+        match Self::load(&path) {
+            Ok(config) => config,
+            Err(RsweeperError::Io(ref e)) if e.kind() == io::ErrorKind::NotFound => Self::default(),
+            Err(e) => {
+                println!("Ignoring {}: {}", path.as_ref().display(), e);
+                Self::default()
+            },
+        }
+    }
+
+    /// Creates the gameboard described by the configuration.
+    pub fn build_board(&self) -> Result<Gameboard, RsweeperError> {
+        let gameboard = Gameboard::try_new(self.size, self.bombs)?;
+        Ok(match self.seed {
+            Some(seed) => Gameboard::new_seeded(self.size, self.bombs, seed),
+            None => gameboard,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameboard::PlayerCell;
+
+    #[test]
+    fn parses_a_valid_config() {
+        let config = GameConfig::parse("size = [16, 16]\nbombs = 40\nseed = 3\ntheme = \"dark.json\"\n").unwrap();
+        assert_eq!(config.size, [16, 16]);
+        assert_eq!(config.bombs, 40);
+        assert_eq!(config.seed, Some(3));
+        assert_eq!(config.theme.as_deref(), Some("dark.json"));
+    }
+
+    #[test]
+    fn missing_fields_are_defaults() {
+        let config = GameConfig::parse("bombs = 20\n").unwrap();
+        assert_eq!(config.size, GameConfig::default().size);
+        assert_eq!(config.seed, None);
+        assert_eq!(config.theme, None);
+    }
+
+    #[test]
+    fn rejects_too_many_bombs() {
+        assert!(matches!(GameConfig::parse("size = [3, 3]\nbombs = 9\n"), Err(RsweeperError::InvalidBoard(_))));
+    }
+
+    #[test]
+    fn falls_back_to_the_default_config() {
+        assert_eq!(GameConfig::load_or_default("does/not/exist.toml"), GameConfig::default());
+    }
+
+    #[test]
+    fn seeded_boards_have_the_same_layout() {
+        let config = GameConfig::parse("size = [9, 9]\nbombs = 10\nseed = 7\n").unwrap();
+        let mut boards = [config.build_board().unwrap(), config.build_board().unwrap()];
+        for board in &mut boards {
+            board.set([4, 4], PlayerCell::Revealed);
+        }
+        for y in 0..9 {
+            for x in 0..9 {
+                assert_eq!(boards[0].peek(x, y).1, boards[1].peek(x, y).1);
+            }
+        }
+    }
+}
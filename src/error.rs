@@ -0,0 +1,42 @@
+//! Errors of the game board API.
+
+use std::{error, fmt, io};
+
+/// The errors returned by the fallible game board functions.
+#[derive(Debug)]
+pub enum RsweeperError {
+    /// The cell at the given position is outside the board.
+    OutOfBounds([usize; 2]),
+    /// The board specification is invalid, e.g., too many bombs.
+    InvalidBoard(String),
+    /// A textual representation could not be parsed.
+    Parse(String),
+    /// An IO operation failed.
+    Io(io::Error),
+}
+
+impl fmt::Display for RsweeperError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutOfBounds(ind) => write!(f, "cell {:?} is out of the board", ind),
+            Self::InvalidBoard(reason) => write!(f, "invalid board: {}", reason),
+            Self::Parse(reason) => write!(f, "parse error: {}", reason),
+            Self::Io(e) => write!(f, "IO error: {}", e),
+        }
+    }
+}
+
+impl error::Error for RsweeperError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for RsweeperError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
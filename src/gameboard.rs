@@ -1,31 +1,34 @@
 //! Game board logic.
 
 use std::cmp::min;
+use std::time::{Duration, Instant};
 
+use chrono::{Datelike, NaiveDate};
 use graphics::types::Color;
-use rand::{self, Rng};
+use rand::{self, Rng, SeedableRng, rngs::StdRng};
+use serde::{Deserialize, Serialize};
+
+use crate::error::RsweeperError;
 
 /// The different values of a cell from the user.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub enum PlayerCell {
     /// Not determined yet, the default value.
+    #[default]
     NotDetermined,
     /// Flagged as containing a bomb.
     Flagged,
     /// Question, not flagged but show a question mark on the cell.
     Question,
+    /// Suspected to contain a bomb, a softer mark than a flag: it does not
+    /// count as a flag, neither in the bombs left nor to win.
+    Suspected,
     /// Revealed, show either the value or the bomb.
     Revealed,
 }
 
-impl Default for PlayerCell {
-    fn default() -> Self {
-        Self::NotDetermined
-    }
-}
-
 /// The actual content of the cell.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum CellContent {
     /// Nothing, but indicates the number of bombs directly around it.
     Nothing(u8),
@@ -41,22 +44,38 @@ impl Default for CellContent {
 
 /// A sweeper cell, containing information about its real value and what the
 /// player thinks about it.
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
 pub struct Cell {
     /// The interaction that the player has with the cell.
     player: PlayerCell,
     /// The actual content of the cell.
     content: CellContent,
+    /// When the cell was revealed, if it is.
+    revealed_at: Option<Instant>,
 }
 
 impl Cell {
     pub fn get_player_cell(&self) -> PlayerCell {
         self.player
     }
+
+    /// Gets when the cell was revealed, if it is.
+    pub fn revealed_at(&self) -> Option<Instant> {
+        self.revealed_at
+    }
+
+    /// Sets the interaction of the player, recording when the cell gets
+    /// revealed.
+    fn set_player(&mut self, val: PlayerCell) {
+        if let (PlayerCell::Revealed, None) = (val, self.revealed_at) {
+            self.revealed_at = Some(Instant::now());
+        }
+        self.player = val;
+    }
 }
 
 /// Indicates the game state.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum GameState {
     /// The initial status, the player did not interaction yet with the board.
     /// The game stays in this state as long as the player did not revealed any
@@ -73,7 +92,190 @@ pub enum GameState {
     Lost,
 }
 
+/// The difficulty presets of the game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Difficulty {
+    /// 9x9 board with 10 bombs.
+    Beginner,
+    /// 16x16 board with 40 bombs.
+    Intermediate,
+    /// 30x16 board with 99 bombs.
+    Expert,
+    /// Any other board.
+    Custom,
+}
+
+impl Difficulty {
+    /// Gets the size and the number of bombs of the preset, `None` for
+    /// `Custom`.
+    pub fn size_and_bombs(self) -> Option<([usize; 2], usize)> {
+        match self {
+            Self::Beginner => Some(([9, 9], 10)),
+            Self::Intermediate => Some(([16, 16], 40)),
+            Self::Expert => Some(([30, 16], 99)),
+            Self::Custom => None,
+        }
+    }
+
+    /// Gets the default `min_opening` of boards of the preset: easier
+    /// presets start with a bigger opening.
+    pub fn default_min_opening(self) -> usize {
+        match self {
+            Self::Beginner => 20,
+            Self::Intermediate => 10,
+            Self::Expert | Self::Custom => 0,
+        }
+    }
+}
+
+/// Why a move did not change the board.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IgnoredReason {
+    /// The game is paused.
+    Paused,
+    /// The game did not start yet, and only starts with a reveal.
+    NotStarted,
+    /// The move has no effect on the board, e.g., revealing a revealed cell.
+    NoEffect,
+}
+
+/// What a move did to the board.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MoveOutcome {
+    /// The move changed the board. If the reveal cascade was cut by
+    /// `max_cascade_per_call`, `pending` has the empty cells it has to
+    /// continue from with `continue_cascade`.
+    Applied {
+        /// The revealed empty cells whose neighbors are left to reveal.
+        pending: Vec<(usize, usize)>,
+        /// The cells whose character or colors changed, row by row, e.g., to
+        /// only redraw them. On a loss, it includes all the bombs shown.
+        changed: Vec<(usize, usize)>,
+    },
+    /// The move was ignored.
+    Ignored(IgnoredReason),
+}
+
+/// A move of the player.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum MoveAction {
+    /// Sets the player input on the cell, see `Gameboard::set`.
+    Set(PlayerCell),
+    /// Chords on the cell, see `Gameboard::chord`.
+    Chord,
+    /// Continues the reveal cascade of a previous move from the cell and the
+    /// other cells it has to continue from, see
+    /// `Gameboard::continue_cascade`.
+    ContinueCascade,
+    /// Reveals the neighbors of the cell, see `Gameboard::reveal_neighbors`.
+    RevealNeighbors,
+    /// Reveals every empty region, see `Gameboard::open_all_zero_regions`.
+    OpenZeroRegions,
+    /// Flags every obvious mine, see `Gameboard::flag_all_obvious_mines`.
+    FlagObviousMines,
+    /// Removes every question mark, see `Gameboard::clear_all_questions`.
+    ClearQuestions,
+    /// Undoes the last move, see `Gameboard::undo`.
+    Undo,
+    /// Redoes the last undone move, see `Gameboard::redo`.
+    Redo,
+    /// Restarts the game, see `Gameboard::restart_same_layout`.
+    Restart,
+    /// Reveals every cell at the end of the game, see
+    /// `Gameboard::reveal_all`.
+    RevealAll,
+}
+
+/// An entry of the event log of the board.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MoveEvent {
+    /// The time of the game when the move was played.
+    pub time: Duration,
+    /// The column of the cell, 0 for moves on the whole board.
+    pub x: usize,
+    /// The row of the cell, 0 for moves on the whole board.
+    pub y: usize,
+    /// The move played.
+    pub action: MoveAction,
+    /// What the move did.
+    pub outcome: MoveOutcome,
+}
+
+/// What the player knows about the neighbors of a cell.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NeighborSummary {
+    /// The number of flagged neighbors.
+    pub flagged: usize,
+    /// The number of revealed neighbors.
+    pub revealed: usize,
+    /// The number of neighbors neither revealed nor flagged.
+    pub unrevealed: usize,
+}
+
+/// What the revealed numbers prove about a cell.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Deduced {
+    /// The cell has no bomb.
+    Safe,
+    /// The cell has a bomb.
+    Mine,
+}
+
+/// A conclusion drawn from a single revealed number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Deduction {
+    /// The revealed cell whose number leads to the conclusion.
+    pub source: (usize, usize),
+    /// The number of the source cell.
+    pub number: u8,
+    /// The cell the conclusion is about.
+    pub target: (usize, usize),
+    /// Whether the target is a mine, otherwise it is safe.
+    pub mine: bool,
+}
+
+/// The symmetries a bomb layout can have.
+#[derive(Clone, Copy, Debug)]
+pub enum Symmetry {
+    /// Mirrored along the horizontal axis, top and bottom halves match.
+    Horizontal,
+    /// Mirrored along the vertical axis, left and right halves match.
+    Vertical,
+    /// Unchanged by a rotation of 180 degrees.
+    Rotational,
+}
+
+/// The state of the board before or after a move, to undo or redo it.
+#[derive(Clone)]
+struct Snapshot {
+    cells: Vec<Cell>,
+    flagged: usize,
+    state: GameState,
+    guesses: usize,
+    started_at: Option<Instant>,
+    ended_at: Option<Instant>,
+    paused_at: Option<Instant>,
+    paused_total: Duration,
+    mercy_moves: usize,
+    mercy_used: usize,
+}
+
+/// A generator of the random coordinates of the bombs, to place them with
+/// any generator, e.g., a scripted one. Implemented by all the generators of
+/// `rand`, the default being a `StdRng`.
+pub trait BoardRng {
+    /// Returns a coordinate between 0 included and `max` excluded.
+    fn gen_coord(&mut self, max: usize) -> usize;
+}
+
+impl<R: Rng + ?Sized> BoardRng for R {
+    fn gen_coord(&mut self, max: usize) -> usize {
+        self.gen_range(0..max)
+    }
+}
+
 /// Stores game board information.
+#[derive(Clone)]
 pub struct Gameboard {
     /// The size of the gameboard.
     pub size: [usize; 2],
@@ -83,14 +285,118 @@ pub struct Gameboard {
     pub flagged: usize,
     /// Indicates the game state.
     pub state: GameState,
-    /// The game cells.
-    cells: Vec<Vec<Cell>>,
+    /// Whether the player cannot place more flags than there are bombs.
+    pub restrict_flags: bool,
+    /// The minimum number of cells uncovered by the first reveal. Bomb
+    /// placement is retried up to `MAX_OPENING_RETRIES` times to meet it,
+    /// after which the largest opening found is kept.
+    pub min_opening: usize,
+    /// Whether the neighbors of the first revealed cell never have a bomb,
+    /// so that it is empty and always opens a cascade.
+    pub guaranteed_opening: bool,
+    /// Whether the bombs left are flagged automatically once all the safe
+    /// cells are revealed, so that the game is won without flagging them.
+    pub auto_flag_on_clear: bool,
+    /// Whether reveals that were not provably safe are counted, see
+    /// `guess_count`. Each reveal then runs the solver, so it is off by
+    /// default.
+    pub track_guesses: bool,
+    /// The maximum number of cells a single move reveals through the
+    /// cascade around empty cells, the rest being revealed by calling
+    /// `continue_cascade`, e.g., to animate it. Unlimited if `None`.
+    pub max_cascade_per_call: Option<usize>,
+    /// The cells the reveal cascade of the current move has to continue
+    /// from, see `max_cascade_per_call`.
+    pending_cascade: Vec<(usize, usize)>,
+    /// The cells the reveal cascade of the last move cut by
+    /// `max_cascade_per_call` has to continue from, see `cascade_to_continue`.
+    last_pending: Vec<(usize, usize)>,
+    /// The depth in `history` of the last move recorded, if its reveal
+    /// cascade was cut by `max_cascade_per_call`: continuing the cascade is
+    /// then part of this move.
+    cascade_move: Option<usize>,
+    /// The number of reveals that were not provably safe.
+    guesses: usize,
+    /// When the bombs were placed, if they are.
+    started_at: Option<Instant>,
+    /// When the game was won or lost, if it is.
+    ended_at: Option<Instant>,
+    /// When the game was paused, if it is.
+    paused_at: Option<Instant>,
+    /// The time spent in previous pauses, excluded from `elapsed`.
+    paused_total: Duration,
+    /// The number of times revealing a bomb is forgiven. Each time, the bomb
+    /// is moved elsewhere and the reveal proceeds as if the cell was safe.
+    pub mercy_moves: usize,
+    /// The number of reveals forgiven by `mercy_moves`.
+    mercy_used: usize,
+    /// The seed of the bomb placement, random if `None`.
+    seed: Option<u64>,
+    /// The number of cells without bomb, computed once the bombs are placed.
+    /// Moving a bomb elsewhere keeps it valid.
+    total_safe: Option<usize>,
+    /// The states of the board before each move, the last move first.
+    history: Vec<Snapshot>,
+    /// The states of the board after each undone move, the last undone move
+    /// first.
+    redo_stack: Vec<Snapshot>,
+    /// The moves played, the first move first.
+    events: Vec<MoveEvent>,
+    /// Whether the board was changed other than by a logged move, e.g., with
+    /// `apply_diff`, in which case the event log cannot be replayed.
+    unlogged_changes: bool,
+    /// Whether diagonal cells are neighbors, counting in the numbers and the
+    /// reveal cascade. Set at construction as it changes the numbers.
+    diagonal_cascade: bool,
+    /// The game cells, row by row. Use `idx` to get the index of a cell.
+    cells: Vec<Cell>,
+}
+
+/// A constraint given by a revealed number: exactly `mines` of the `cells`
+/// contain a bomb. Cells are indices in the enclosing `ConstraintGroup`.
+struct Constraint {
+    cells: Vec<usize>,
+    mines: usize,
 }
 
+/// Unknown cells linked together by the constraints of the revealed numbers
+/// around them. Groups are independent from each other.
+struct ConstraintGroup {
+    /// The positions of the unknown cells of the group.
+    cells: Vec<(usize, usize)>,
+    /// The constraints over these cells.
+    constraints: Vec<Constraint>,
+}
+
+/// The maximum number of unknown cells in a constraint group for which all
+/// mine assignments are enumerated. Larger groups are solved by windows of
+/// neighboring constraints, see `local_windows`.
+const CSP_MAX_GROUP_SIZE: usize = 20;
+
+/// The default maximum number of cells of a board built with
+/// `Gameboard::try_new`, preventing huge allocations from user input.
+pub const DEFAULT_MAX_CELLS: usize = 1_000_000;
+
+/// The maximum number of times bomb placement is retried to get an opening
+/// of at least `min_opening` cells.
+pub const MAX_OPENING_RETRIES: usize = 100;
+
+/// The initial value of the 64-bit FNV-1a hash used by `layout_hash`.
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// The multiplier of the 64-bit FNV-1a hash used by `layout_hash`.
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// The optional character with its own font color, and the background color
+/// showing a cell.
+pub type CellDisplay = (Option<(char, Color)>, Color);
+
 const BOMB_BACKGROUND: Color = [0.9, 0.0, 0.0, 1.0];
 const ND_BACKGROUND: Color = [1.0, 1.0, 1.0, 1.0];
 const REV_BACKGROUND: Color = [0.7, 0.7, 0.7, 1.0];
 const FLAGGED_BACKGROUND: Color = [1.0, 0.64, 0.0, 1.0];
+const SUSPECTED_BACKGROUND: Color = [1.0, 0.9, 0.6, 1.0];
+const WRONG_FLAG_BACKGROUND: Color = [0.6, 0.2, 0.8, 1.0];
 const BLACK: Color = [0.0, 0.0, 0.1, 1.0];
 
 const ONE_COLOR: Color = [0.0, 0.0, 1.0, 1.0];
@@ -104,6 +410,11 @@ const EIGTH_COLOR: Color = [1.0, 0.6, 0.6, 1.0];
 
 impl Gameboard {
     /// Creates a new game board.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are too many bombs to be placed, see `try_new` for a
+    /// fallible version.
     pub fn new(size: [usize; 2], bombs: usize) -> Self {
         assert!(size[0] * size[1] > bombs, "Too many bombs to be placed");
         Self {
@@ -111,44 +422,273 @@ impl Gameboard {
             bombs,
             flagged: 0,
             state: GameState::Initial,
-            cells: vec![vec![Cell::default(); size[1]]; size[0]],
+            restrict_flags: true,
+            min_opening: 0,
+            guaranteed_opening: false,
+            auto_flag_on_clear: false,
+            track_guesses: false,
+            max_cascade_per_call: None,
+            pending_cascade: Vec::new(),
+            last_pending: Vec::new(),
+            cascade_move: None,
+            guesses: 0,
+            started_at: None,
+            ended_at: None,
+            paused_at: None,
+            paused_total: Duration::from_secs(0),
+            mercy_moves: 0,
+            mercy_used: 0,
+            seed: None,
+            total_safe: None,
+            history: Vec::new(),
+            redo_stack: Vec::new(),
+            events: Vec::new(),
+            unlogged_changes: false,
+            diagonal_cascade: true,
+            cells: vec![Cell::default(); size[0] * size[1]],
         }
     }
 
-    fn count_neighbor_bombs(&self, x: usize, y: usize) -> u8 {
-        let mut res = 0;
-        for ny in y.saturating_sub(1)..=min(y + 1, self.size[1] - 1) {
-            for nx in x.saturating_sub(1)..=min(x + 1, self.size[0] - 1) {
-                // We do not handle ourselve, but if we are a bomb this has
-                // no much sense.
-                match self.get_cell(nx, ny).content {
-                    CellContent::Bomb => res += 1,
-                    _ => {},
-                }
+    /// Creates a new game board whose bomb placement is determined by `seed`
+    /// and the first revealed cell, to replay the same game.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are too many bombs to be placed.
+    pub fn new_seeded(size: [usize; 2], bombs: usize, seed: u64) -> Self {
+        let mut res = Self::new(size, bombs);
+        res.seed = Some(seed);
+        res
+    }
+
+    /// Creates a new game board where only the 4 orthogonal cells are
+    /// neighbors: numbers count the bombs above, below, left and right of
+    /// cells, and the reveal cascade does not go diagonally.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are too many bombs to be placed.
+    pub fn new_orthogonal(size: [usize; 2], bombs: usize) -> Self {
+        let mut res = Self::new(size, bombs);
+        res.diagonal_cascade = false;
+        res
+    }
+
+    /// Whether diagonal cells are neighbors, see `new_orthogonal`.
+    pub fn diagonal_cascade(&self) -> bool {
+        self.diagonal_cascade
+    }
+
+    /// Creates a new game board with the size, bombs and default
+    /// `min_opening` of a preset. Returns `None` for `Custom`.
+    pub fn from_difficulty(difficulty: Difficulty) -> Option<Self> {
+        let (size, bombs) = difficulty.size_and_bombs()?;
+        let mut res = Self::new(size, bombs);
+        res.min_opening = difficulty.default_min_opening();
+        Some(res)
+    }
+
+    /// Same as `from_difficulty`, with bombs placed from `seed` like
+    /// `new_seeded`.
+    pub fn from_difficulty_seeded(difficulty: Difficulty, seed: u64) -> Option<Self> {
+        let mut res = Self::from_difficulty(difficulty)?;
+        res.seed = Some(seed);
+        Some(res)
+    }
+
+    /// Creates the daily challenge board of a preset for `date`, seeded with
+    /// `daily_seed(date)` so that everybody gets the same board on the same
+    /// day. Returns `None` for `Custom`.
+    pub fn daily(difficulty: Difficulty, date: NaiveDate) -> Option<Self> {
+        Self::from_difficulty_seeded(difficulty, Self::daily_seed(date))
+    }
+
+    /// Creates a board of a preset whose 3BV, see `compute_3bv`, is between
+    /// `min_3bv` and `max_3bv` included, e.g., for curated packs. Up to
+    /// `max_tries` layouts are generated from seeds derived from `seed`,
+    /// keeping the center of the board as the safe first click. The bombs are
+    /// placed, and the game is started. Returns `None` for `Custom` or if no
+    /// layout is in the range.
+    pub fn new_with_3bv_range(difficulty: Difficulty, min_3bv: usize, max_3bv: usize, seed: u64, max_tries: usize) -> Option<Self> {
+        for i in 0..max_tries {
+            let mut res = Self::from_difficulty(difficulty)?;
+            let first_click = [res.size[0] / 2, res.size[1] / 2];
+            let mut rng = StdRng::seed_from_u64(seed.wrapping_add(i as u64));
+            res.place_bombs_with_rng(first_click, &mut rng).ok()?;
+            if (min_3bv..=max_3bv).contains(&res.compute_3bv()) {
+                return Some(res);
             }
         }
-        res
+        None
+    }
+
+    /// Derives the seed of the daily challenge of `date`: the date written as
+    /// the number YYYYMMDD goes through the SplitMix64 finalizer, so that
+    /// consecutive days get unrelated seeds.
+    pub fn daily_seed(date: NaiveDate) -> u64 {
+        let mut z = date.year() as u64 * 10_000 + date.month() as u64 * 100 + date.day() as u64;
+        z = z.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Gets the seed of the bomb placement, `None` if it is random.
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// Sets the seed of the bomb placement, as if the board was created with
+    /// `new_seeded`. Ignored once the bombs are placed, in which case `false`
+    /// is returned.
+    pub fn set_seed(&mut self, seed: u64) -> bool {
+        if let GameState::Initial = self.state {
+            self.seed = Some(seed);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Changes the number of bombs placed by the first reveal, e.g., from a
+    /// difficulty slider. Returns an error if the bombs are already placed or
+    /// if there are too many bombs to be placed.
+    pub fn set_bombs(&mut self, bombs: usize) -> Result<(), RsweeperError> {
+        if self.state != GameState::Initial {
+            return Err(RsweeperError::InvalidBoard("bombs are already placed".to_string()));
+        }
+        if self.cells.len() <= bombs {
+            return Err(RsweeperError::InvalidBoard(format!("too many bombs to be placed: {}", bombs)));
+        }
+        self.bombs = bombs;
+        Ok(())
+    }
+
+    /// Creates a new game board, or returns an error if the board is empty,
+    /// has more than `DEFAULT_MAX_CELLS` cells or there are too many bombs to
+    /// be placed.
+    pub fn try_new(size: [usize; 2], bombs: usize) -> Result<Self, RsweeperError> {
+        Self::try_new_with_max_cells(size, bombs, DEFAULT_MAX_CELLS)
+    }
+
+    /// Same as `try_new`, but rejects boards having more than `max_cells`
+    /// cells instead of `DEFAULT_MAX_CELLS`.
+    pub fn try_new_with_max_cells(size: [usize; 2], bombs: usize, max_cells: usize) -> Result<Self, RsweeperError> {
+        if size[0] == 0 || size[1] == 0 {
+            return Err(RsweeperError::InvalidBoard(format!("empty board {:?}", size)));
+        }
+        match size[0].checked_mul(size[1]) {
+            Some(cells) if cells <= max_cells => {},
+            _ => return Err(RsweeperError::InvalidBoard(
+                format!("board {:?} has more than {} cells", size, max_cells)
+            )),
+        }
+        if size[0] * size[1] <= bombs {
+            return Err(RsweeperError::InvalidBoard(format!("too many bombs to be placed: {}", bombs)));
+        }
+        Ok(Self::new(size, bombs))
+    }
+
+    fn count_neighbor_bombs(&self, x: usize, y: usize) -> u8 {
+        self.neighbor_cells(x, y)
+            .filter(|(_, _, cell)| matches!(cell.content, CellContent::Bomb))
+            .count() as u8
+    }
+
+    /// Returns an iterator over the positions of the neighbors of a cell, the
+    /// cell itself excluded. Cells on the edges have fewer neighbors, and
+    /// diagonal cells are not neighbors on boards built with
+    /// `new_orthogonal`.
+    pub fn neighbors(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
+        let (w, h) = (self.size[0], self.size[1]);
+        let diagonal = self.diagonal_cascade;
+        (y.saturating_sub(1)..=min(y + 1, h - 1))
+            .flat_map(move |ny| (x.saturating_sub(1)..=min(x + 1, w - 1)).map(move |nx| (nx, ny)))
+            .filter(move |&pos| pos != (x, y))
+            .filter(move |&(nx, ny)| diagonal || nx == x || ny == y)
+    }
+
+    /// Returns an iterator over the neighbors of a cell with their content,
+    /// the cell itself excluded.
+    pub fn neighbor_cells(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize, &Cell)> {
+        self.neighbors(x, y).map(move |(nx, ny)| (nx, ny, self.get_cell(nx, ny)))
     }
 
     /// Gets a immutable reference to a Cell.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell is out of the board, see `try_get_cell` for a
+    /// fallible version.
     pub fn get_cell(&self, x: usize, y: usize) -> &Cell {
-        & self.cells[y][x]
+        & self.cells[self.idx(x, y)]
     }
 
     /// Gets a mutable reference to a Cell.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell is out of the board.
     pub fn get_mut_cell(&mut self, x: usize, y: usize) -> &mut Cell {
-        &mut self.cells[y][x]
+        let idx = self.idx(x, y);
+        &mut self.cells[idx]
     }
 
-    /// Initialize the cells.
-    fn init(&mut self) {
-        println!("Starting init");
+    /// Gets the index of a cell in `cells`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell is out of the board, as it would otherwise alias
+    /// another cell.
+    fn idx(&self, x: usize, y: usize) -> usize {
+        assert!(x < self.size[0] && y < self.size[1], "cell out of the board");
+        y * self.size[0] + x
+    }
+
+    /// Gets copies of both the player view and the content of a cell, without
+    /// any side effect on the game.
+    pub fn peek(&self, x: usize, y: usize) -> (PlayerCell, CellContent) {
+        let cell = self.get_cell(x, y);
+        (cell.player, cell.content)
+    }
+
+    /// Checks that the cell at `ind` is inside the board.
+    fn check_bounds(&self, ind: [usize; 2]) -> Result<(), RsweeperError> {
+        if ind[0] < self.size[0] && ind[1] < self.size[1] {
+            Ok(())
+        } else {
+            Err(RsweeperError::OutOfBounds(ind))
+        }
+    }
+
+    /// Gets a immutable reference to a Cell, or an error if it is out of the
+    /// board.
+    pub fn try_get_cell(&self, x: usize, y: usize) -> Result<&Cell, RsweeperError> {
+        self.check_bounds([x, y])?;
+        Ok(self.get_cell(x, y))
+    }
+
+    /// Places the bombs at random on cells not revealed by the player nor in
+    /// `keep_free`. If fewer such cells than `bombs` are left, `bombs` is
+    /// reduced to their number, so that the placement always terminates.
+    fn place_bombs<R: BoardRng + ?Sized>(&mut self, rng: &mut R, keep_free: &[(usize, usize)]) {
+        // `bombs` is public and may have been changed since the board was
+        // built: make sure there is room for them, or the loop never ends.
+        let free = self.cells.iter().enumerate()
+            .filter(|(i, cell)| {
+                cell.player != PlayerCell::Revealed && cell.content != CellContent::Bomb
+                    && !keep_free.contains(&(i % self.size[0], i / self.size[0]))
+            })
+            .count();
+        self.bombs = min(self.bombs, free);
         // This is very unefficient to do so, but anyway.
-        let mut rng = rand::thread_rng();
         let mut placed = 0;
         while placed < self.bombs {
-            let x = rng.gen_range(0..self.size[0]);
-            let y = rng.gen_range(0..self.size[1]);
+            let x = rng.gen_coord(self.size[0]);
+            let y = rng.gen_coord(self.size[1]);
+            if keep_free.contains(&(x, y)) {
+                continue;
+            }
             let cell = self.get_mut_cell(x, y);
             // Place a bomb only if
             // 1) the cell was not revealed by the player
@@ -163,9 +703,210 @@ impl Gameboard {
                 }
             }
         }
+    }
+
+    /// Places the bombs at the given positions instead of randomly, and
+    /// starts the game: the first reveal keeps this layout. `bombs` becomes
+    /// the number of distinct positions. Returns an error if the bombs are
+    /// already placed, a position is out of the board or no cell is left
+    /// without bomb.
+    pub fn place_bombs_from_positions(&mut self, positions: &[(usize, usize)]) -> Result<(), RsweeperError> {
+        if self.state != GameState::Initial {
+            return Err(RsweeperError::InvalidBoard("bombs are already placed".to_string()));
+        }
+        for &(x, y) in positions {
+            self.check_bounds([x, y])?;
+        }
+        for &(x, y) in positions {
+            self.get_mut_cell(x, y).content = CellContent::Bomb;
+        }
+        let bombs = self.actual_bomb_count();
+        if bombs >= self.cells.len() {
+            for cell in self.cells.iter_mut() {
+                cell.content = CellContent::default();
+            }
+            return Err(RsweeperError::InvalidBoard(format!("too many bombs to be placed: {}", bombs)));
+        }
+        self.bombs = bombs;
+        self.total_safe = Some(self.cells.len() - bombs);
+        self.recompute_numbers();
+        self.state = GameState::Alive;
+        self.started_at = Some(Instant::now());
+        self.unlogged_changes = true;
+        Ok(())
+    }
+
+    /// Places the bombs with `rng` instead of the seeded or random generator
+    /// of the board, keeping `first_click` safe, and starts the game: the
+    /// first reveal keeps this layout. Returns an error if the bombs are
+    /// already placed or the cell is out of the board.
+    pub fn place_bombs_with_rng<R: BoardRng + ?Sized>(&mut self, first_click: [usize; 2], rng: &mut R) -> Result<(), RsweeperError> {
+        if self.state != GameState::Initial {
+            return Err(RsweeperError::InvalidBoard("bombs are already placed".to_string()));
+        }
+        self.check_bounds(first_click)?;
+        // Bombs are not placed on revealed cells.
+        let player = self.get_cell(first_click[0], first_click[1]).player;
+        self.get_mut_cell(first_click[0], first_click[1]).player = PlayerCell::Revealed;
+        self.init_with_rng(first_click, rng);
+        self.get_mut_cell(first_click[0], first_click[1]).player = player;
+        self.unlogged_changes = true;
+        Ok(())
+    }
+
+    /// Returns the generator of the random choices made after the bomb
+    /// placement, e.g., where a bomb is relocated. On seeded boards, it is
+    /// derived from the seed and `salt`, so that replaying the same moves
+    /// makes the same choices.
+    fn derived_rng(&self, salt: u64) -> StdRng {
+        match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed ^ salt.wrapping_mul(0x9E37_79B9_7F4A_7C15)),
+            None => StdRng::from_entropy(),
+        }
+    }
+
+    /// Initialize the cells, given the first cell revealed by the player.
+    fn init(&mut self, ind: [usize; 2]) {
+        let mut rng = match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        self.init_with_rng(ind, &mut rng);
+    }
+
+    /// Initialize the cells with bombs placed by `rng`, given the first cell
+    /// revealed by the player.
+    fn init_with_rng<R: BoardRng + ?Sized>(&mut self, ind: [usize; 2], rng: &mut R) {
+        println!("Starting init");
+        // Keep the neighbors of the first cell free of bombs for it to open a
+        // cascade, if there is room for the bombs elsewhere.
+        let mut keep_free: Vec<(usize, usize)> = Vec::new();
+        if self.guaranteed_opening {
+            keep_free = self.neighbors(ind[0], ind[1]).collect();
+            if self.bombs + keep_free.len() >= self.cells.len() {
+                keep_free.clear();
+            }
+        }
+        // The best layout found so far when looking for a large enough
+        // opening, with the size of its opening.
+        let mut best: Option<(usize, Vec<Cell>)> = None;
+        for _ in 0..=MAX_OPENING_RETRIES {
+            for cell in self.cells.iter_mut() {
+                cell.content = CellContent::default();
+            }
+            self.place_bombs(rng, &keep_free);
+            // And now compute the neighbors.
+            self.recompute_numbers();
+
+            let opening = self.preview_reveal(ind[0], ind[1]);
+            if opening >= self.min_opening {
+                best = None;
+                break;
+            }
+            if best.as_ref().is_none_or(|(size, _)| opening > *size) {
+                best = Some((opening, self.cells.clone()));
+            }
+        }
+        if let Some((_, cells)) = best {
+            // Do our best.
+            self.cells = cells;
+        }
         println!("Bomb placed");
+        self.total_safe = Some(self.cells.len() - self.actual_bomb_count());
+
+        // Now the game starts!
+        self.state = GameState::Alive;
+        self.started_at = Some(Instant::now());
+        println!("Init done!");
+    }
+
+    /// Returns the number of cells that revealing the cell would uncover,
+    /// including itself and the cascade around empty cells, without
+    /// modifying the board.
+    fn preview_reveal(&self, x: usize, y: usize) -> usize {
+        let mut visited = self.cascade_walls();
+        self.flood(x, y, &mut visited).len()
+    }
+
+    /// Marks the cells a reveal cascade does not go through: revealed cells
+    /// are already handled, and flags are walls.
+    fn cascade_walls(&self) -> Vec<bool> {
+        self.cells.iter()
+            .map(|cell| matches!(cell.player, PlayerCell::Revealed | PlayerCell::Flagged))
+            .collect()
+    }
+
+    /// Returns the indices of the cells uncovered by the cascade starting at
+    /// the cell, skipping and marking the `visited` ones.
+    fn flood(&self, x: usize, y: usize, visited: &mut [bool]) -> Vec<usize> {
+        let start = self.idx(x, y);
+        visited[start] = true;
+        let mut region = vec![start];
+        let mut to_expand = vec![(x, y)];
+        while let Some((x, y)) = to_expand.pop() {
+            if let CellContent::Nothing(0) = self.get_cell(x, y).content {
+                for (nx, ny) in self.neighbors(x, y) {
+                    let idx = self.idx(nx, ny);
+                    if visited[idx] {
+                        continue;
+                    }
+                    visited[idx] = true;
+                    region.push(idx);
+                    to_expand.push((nx, ny));
+                }
+            }
+        }
+        region
+    }
+
+    /// Returns the number of cells of the biggest opening, i.e., a region of
+    /// empty cells with its numbered border, regardless of what the player
+    /// revealed.
+    pub fn largest_opening(&self) -> usize {
+        let mut visited = vec![false; self.cells.len()];
+        let mut largest = 0;
+        for y in 0..self.size[1] {
+            for x in 0..self.size[0] {
+                let idx = self.idx(x, y);
+                if visited[idx] || self.cells[idx].content != CellContent::Nothing(0) {
+                    continue;
+                }
+                let region = self.flood(x, y, &mut visited);
+                largest = largest.max(region.len());
+                // Numbered cells may also border other openings.
+                for i in region {
+                    if self.cells[i].content != CellContent::Nothing(0) {
+                        visited[i] = false;
+                    }
+                }
+            }
+        }
+        largest
+    }
+
+    /// Computes the 3BV of the layout, i.e., the minimum number of clicks
+    /// needed to reveal all the safe cells without flagging: one per opening,
+    /// plus one per numbered cell not bordering any opening.
+    pub fn compute_3bv(&self) -> usize {
+        let mut visited = vec![false; self.cells.len()];
+        let mut res = 0;
+        for y in 0..self.size[1] {
+            for x in 0..self.size[0] {
+                let idx = self.idx(x, y);
+                if !visited[idx] && self.cells[idx].content == CellContent::Nothing(0) {
+                    self.flood(x, y, &mut visited);
+                    res += 1;
+                }
+            }
+        }
+        res + self.cells.iter().zip(&visited)
+            .filter(|(cell, &visited)| !visited && cell.content != CellContent::Bomb)
+            .count()
+    }
 
-        // And now compute the neighbors.
+    /// Computes the number of neighbor bombs of every cell without bomb,
+    /// e.g., after bombs were moved.
+    pub fn recompute_numbers(&mut self) {
         for y in 0..self.size[1] {
             for x in 0..self.size[0] {
                 let cell = self.get_cell(x, y);
@@ -179,10 +920,148 @@ impl Gameboard {
                 }
             }
         }
+    }
 
-        // Now the game starts!
-        self.state = GameState::Alive;
-        println!("Init done!");
+    /// Computes the number of neighbor bombs of the cell and its neighbors,
+    /// the only ones affected when a bomb is added or removed at the cell.
+    pub fn recompute_numbers_around(&mut self, x: usize, y: usize) {
+        let around: Vec<(usize, usize)> = self.neighbors(x, y).chain(Some((x, y))).collect();
+        for (nx, ny) in around {
+            if let CellContent::Nothing(_) = self.get_cell(nx, ny).content {
+                let new_val = self.count_neighbor_bombs(nx, ny);
+                self.get_mut_cell(nx, ny).content = CellContent::Nothing(new_val);
+            }
+        }
+    }
+
+    /// Sets the content of a cell, keeping `bombs` consistent, to build
+    /// scenarios without going through the random bomb placement. Numbers are
+    /// not updated, call `recompute_numbers` once all bombs are placed, or
+    /// `recompute_numbers_around` for each changed cell, and set `state` to
+    /// `Alive` so that the first reveal keeps the layout.
+    #[cfg(any(test, feature = "scenario"))]
+    pub fn set_cell_content(&mut self, x: usize, y: usize, content: CellContent) {
+        let cell = self.get_mut_cell(x, y);
+        let was_bomb = matches!(cell.content, CellContent::Bomb);
+        cell.content = content;
+        match (was_bomb, content) {
+            (false, CellContent::Bomb) => self.bombs += 1,
+            (true, CellContent::Nothing(_)) => self.bombs -= 1,
+            _ => {},
+        }
+        self.total_safe = None;
+        self.unlogged_changes = true;
+    }
+
+    /// Sets what the player did with a cell, keeping `flagged` consistent,
+    /// to build scenarios continuing a game. Unlike a move, it is not
+    /// recorded and does not cascade, but the game is lost or won as usual
+    /// if it is `Alive`, e.g., when revealing a bomb.
+    #[cfg(any(test, feature = "scenario"))]
+    pub fn set_player(&mut self, col: usize, row: usize, state: PlayerCell) {
+        let cell = self.get_mut_cell(col, row);
+        let was_flagged = matches!(cell.player, PlayerCell::Flagged);
+        cell.set_player(state);
+        match (was_flagged, state) {
+            (false, PlayerCell::Flagged) => self.flagged += 1,
+            (true, PlayerCell::Flagged) | (false, _) => {},
+            (true, _) => self.flagged -= 1,
+        }
+        self.unlogged_changes = true;
+        self.update_state([col, row]);
+    }
+
+    /// Loses the game by revealing its first bomb, row by row, e.g., to test
+    /// the end screens. Bombs are placed first if they are not yet.
+    #[cfg(any(test, feature = "scenario"))]
+    pub fn force_lose(&mut self) {
+        if let GameState::Initial = self.state {
+            self.init([0, 0]);
+        }
+        if let Some(cell) = self.cells.iter_mut().find(|cell| cell.content == CellContent::Bomb) {
+            cell.set_player(PlayerCell::Revealed);
+        }
+        self.state = GameState::Lost;
+        self.ended_at = Some(Instant::now());
+        self.flagged = self.count_total_flags();
+        self.unlogged_changes = true;
+    }
+
+    /// Moves the bomb at `ind` to a random cell the player did not interact
+    /// with yet, and updates the neighbor counts. Returns `false` if there is
+    /// no such cell, in which case the board is left untouched.
+    fn relocate_bomb(&mut self, ind: [usize; 2]) -> bool {
+        let mut candidates = Vec::new();
+        for y in 0..self.size[1] {
+            for x in 0..self.size[0] {
+                if [x, y] == ind {
+                    continue;
+                }
+                let cell = self.get_cell(x, y);
+                if let (PlayerCell::NotDetermined, CellContent::Nothing(_)) = (cell.player, cell.content) {
+                    candidates.push([x, y]);
+                }
+            }
+        }
+        if candidates.is_empty() {
+            return false;
+        }
+
+        let salt = (self.mercy_moves as u64) << 32 | self.idx(ind[0], ind[1]) as u64;
+        let mut rng = self.derived_rng(salt);
+        let new_ind = candidates[rng.gen_range(0..candidates.len())];
+        self.get_mut_cell(new_ind[0], new_ind[1]).content = CellContent::Bomb;
+        self.get_mut_cell(ind[0], ind[1]).content = CellContent::Nothing(0);
+        self.recompute_numbers_around(ind[0], ind[1]);
+        self.recompute_numbers_around(new_ind[0], new_ind[1]);
+        true
+    }
+
+    /// Enlarges the board by `add_cols` columns on the right and `add_rows`
+    /// rows at the bottom, and places `extra_bombs` bombs at random in the
+    /// new cells, as many as fit, e.g., for a mode where the board grows.
+    /// Before the first reveal, only the bomb count is increased. Does nothing
+    /// once the game is won or lost. The undo history is dropped, as it does
+    /// not match the new size, and the board cannot be replayed anymore.
+    pub fn expand(&mut self, add_cols: usize, add_rows: usize, extra_bombs: usize) {
+        if let GameState::Won | GameState::Lost = self.state {
+            return;
+        }
+        let old_size = self.size;
+        let size = [old_size[0] + add_cols, old_size[1] + add_rows];
+        let mut cells = vec![Cell::default(); size[0] * size[1]];
+        for y in 0..old_size[1] {
+            for x in 0..old_size[0] {
+                cells[y * size[0] + x] = self.cells[self.idx(x, y)];
+            }
+        }
+        self.size = size;
+        self.cells = cells;
+        self.history.clear();
+        self.redo_stack.clear();
+        self.unlogged_changes = true;
+
+        let mut candidates = Vec::new();
+        for y in 0..size[1] {
+            for x in 0..size[0] {
+                if x >= old_size[0] || y >= old_size[1] {
+                    candidates.push([x, y]);
+                }
+            }
+        }
+        let extra_bombs = min(extra_bombs, candidates.len());
+        self.bombs += extra_bombs;
+        if let GameState::Initial = self.state {
+            // The bombs are placed by the first reveal.
+            return;
+        }
+        let mut rng = self.derived_rng((size[0] as u64) << 32 | size[1] as u64);
+        for _ in 0..extra_bombs {
+            let ind = candidates.swap_remove(rng.gen_range(0..candidates.len()));
+            self.get_mut_cell(ind[0], ind[1]).content = CellContent::Bomb;
+        }
+        self.recompute_numbers();
+        self.total_safe = Some(self.cells.len() - self.actual_bomb_count());
     }
 
     /// Update the state of the gameboard.
@@ -196,11 +1075,27 @@ impl Gameboard {
                 if let CellContent::Bomb = cell.content {
                     // Too bad!
                     self.state = GameState::Lost;
+                    self.ended_at = Some(Instant::now());
+                    self.flagged = self.count_total_flags();
                     println!("Too bad, you lost!");
                     return;
                 }
             }
 
+            // Revealing all the safe cells is enough when asked to flag the
+            // bombs left.
+            if self.auto_flag_on_clear {
+                let cleared = self.cells.iter().all(|cell| match cell.content {
+                    CellContent::Nothing(_) => cell.player == PlayerCell::Revealed,
+                    CellContent::Bomb => true,
+                });
+                if cleared {
+                    for cell in self.cells.iter_mut().filter(|cell| cell.content == CellContent::Bomb) {
+                        cell.set_player(PlayerCell::Flagged);
+                    }
+                }
+            }
+
             // Did the player won?
             // Actually, we can just look at player views, if we only have
             // Revealed and exactly `bombs` Flagged, the player wins.
@@ -218,7 +1113,7 @@ impl Gameboard {
                         },
                         PlayerCell::Revealed => {},
                         // If some are not Revealed nor Flagged, then the game
-                        // is not over.
+                        // is not over. Suspected bombs are not flags.
                         _ => over = false,
                     }
                 }
@@ -228,76 +1123,467 @@ impl Gameboard {
             if over && self.flagged == self.bombs {
                 // If we arrive here, it means the player won!
                 self.state = GameState::Won;
+                self.ended_at = Some(Instant::now());
                 println!("Hoora, you won!");
             }
         }
 
     }
 
+    /// Reveals the cascade around the empty cell at the given position, the
+    /// cell itself counting in `max_cascade_per_call`.
     fn reveal_with_no_neighbors(&mut self, x: usize, y: usize) {
-        for ny in y.saturating_sub(1)..=min(y + 1, self.size[1] - 1) {
-            for nx in x.saturating_sub(1)..=min(x + 1, self.size[0] - 1) {
-                // Only handle cells that are not revealed, otherwise we will
-                // loop forever.
-                if let PlayerCell::Revealed = self.get_cell(nx, ny).player {
+        let budget = self.max_cascade_per_call.map(|max| max.saturating_sub(1));
+        self.cascade(vec![(x, y)], budget);
+    }
+
+    /// Reveals the cascade around the given revealed empty cells, stopping
+    /// after `budget` reveals if any. The empty cells left to expand are
+    /// added to `pending_cascade`. The flood is iterative and never visits a
+    /// cell twice, so it terminates whatever the neighborhoods are, without
+    /// growing the call stack.
+    fn cascade(&mut self, mut to_expand: Vec<(usize, usize)>, budget: Option<usize>) {
+        let mut visited = self.cascade_walls();
+        for &(x, y) in to_expand.iter() {
+            let idx = self.idx(x, y);
+            visited[idx] = true;
+        }
+        let mut revealed = 0;
+        'expand: while let Some((x, y)) = to_expand.pop() {
+            if self.get_cell(x, y).content != CellContent::Nothing(0) {
+                continue;
+            }
+            let neighbors: Vec<(usize, usize)> = self.neighbors(x, y).collect();
+            for (nx, ny) in neighbors {
+                let idx = self.idx(nx, ny);
+                if visited[idx] {
                     continue;
                 }
-                self.get_mut_cell(nx, ny).player = PlayerCell::Revealed;
-                match self.get_cell(nx, ny).content {
-                    CellContent::Nothing(0) => self.reveal_with_no_neighbors(nx, ny),
-                    _ => {},
+                if budget == Some(revealed) {
+                    // Some neighbors of this cell are left.
+                    to_expand.push((x, y));
+                    break 'expand;
+                }
+                visited[idx] = true;
+                self.cells[idx].set_player(PlayerCell::Revealed);
+                revealed += 1;
+                if let CellContent::Nothing(0) = self.cells[idx].content {
+                    to_expand.push((nx, ny));
                 }
             }
         }
+        self.pending_cascade.extend(to_expand);
     }
 
-    /// Sets the player input.
-    pub fn set(&mut self, ind: [usize; 2], val: PlayerCell) {
-        if let GameState::Initial = self.state {
-            // If the game is in Initial state and the value is not a Revealed
-            // one, do nothing.
-            match val {
-                PlayerCell::Revealed => {
-                    // Record that we revealed a cell, and then determine the
-                    // bomb positions.
-                    let cell = self.get_mut_cell(ind[0], ind[1]);
-                    cell.player = PlayerCell::Revealed;
-                    self.init();
-                    // Only perform the optimization if the player has some luck.
-                    match self.get_cell(ind[0], ind[1]).content {
-                        CellContent::Nothing(0) => self.reveal_with_no_neighbors(ind[0], ind[1]),
-                        _ => {},
+    /// Continues a reveal cascade cut by `max_cascade_per_call` from the
+    /// `pending` cells of the outcome of a move, revealing at most
+    /// `max_cascade_per_call` more cells. If no other move was recorded
+    /// since, this is part of the move that started the cascade, so that
+    /// undoing it reverts the whole opening; otherwise it is a move of its
+    /// own. Pending cells that are not revealed anymore, e.g., as the move
+    /// was undone, are skipped.
+    pub fn continue_cascade(&mut self, pending: &[(usize, usize)]) -> MoveOutcome {
+        // Only the cells given by the board are replayed.
+        if pending != self.last_pending.as_slice() {
+            self.unlogged_changes = true;
+        }
+        let outcome = self.continue_cascade_unlogged(pending);
+        if let Some(&(x, y)) = pending.first() {
+            self.log([x, y], MoveAction::ContinueCascade, outcome.clone());
+        }
+        outcome
+    }
+
+    /// Same as `continue_cascade`, without adding the move to the event log.
+    fn continue_cascade_unlogged(&mut self, pending: &[(usize, usize)]) -> MoveOutcome {
+        if self.is_paused() {
+            return MoveOutcome::Ignored(IgnoredReason::Paused);
+        }
+        let pending: Vec<(usize, usize)> = pending.iter()
+            .copied()
+            .filter(|&(x, y)| self.get_cell(x, y).player == PlayerCell::Revealed)
+            .collect();
+        match (self.state, pending.first()) {
+            (GameState::Alive, Some(&(x, y))) => {
+                let before = self.snapshot();
+                self.cascade(pending, self.max_cascade_per_call);
+                self.update_state([x, y]);
+                let outcome = if self.cascade_move == Some(self.history.len()) {
+                    // The state before the move is already recorded.
+                    let outcome = self.outcome_since(&before);
+                    if let MoveOutcome::Applied { .. } = outcome {
+                        self.redo_stack.clear();
+                    }
+                    outcome
+                } else {
+                    self.record(before)
+                };
+                if let MoveOutcome::Applied { pending, .. } = &outcome {
+                    self.last_pending = pending.clone();
+                    if pending.is_empty() {
+                        self.cascade_move = None;
                     }
                 }
-                _ => {}
-            }
-            return;
+                outcome
+            },
+            _ => MoveOutcome::Ignored(IgnoredReason::NoEffect),
         }
+    }
 
-        // We can only set something if we are in the Alive state.
+    /// Gets the cells the reveal cascade of the last move cut by
+    /// `max_cascade_per_call` has to continue from, i.e., the `pending` cells
+    /// of its outcome, or of the outcome of its last continuation.
+    pub fn cascade_to_continue(&self) -> &[(usize, usize)] {
+        &self.last_pending
+    }
+
+    /// Sets the player input, or returns an error if the cell is out of the
+    /// board.
+    pub fn try_set(&mut self, ind: [usize; 2], val: PlayerCell) -> Result<MoveOutcome, RsweeperError> {
+        self.check_bounds(ind)?;
+        Ok(self.set(ind, val))
+    }
+
+    /// Sets the player input. If it changes the board, the move can be
+    /// undone, and the moves previously undone cannot be redone anymore.
+    /// Moves are ignored while the game is paused, and before the first
+    /// reveal unless they are reveals.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell is out of the board, see `try_set` for a fallible
+    /// version.
+    pub fn set(&mut self, ind: [usize; 2], val: PlayerCell) -> MoveOutcome {
+        let outcome = self.set_unlogged(ind, val);
+        self.log(ind, MoveAction::Set(val), outcome.clone());
+        outcome
+    }
+
+    /// Same as `set`, without adding the move to the event log.
+    fn set_unlogged(&mut self, ind: [usize; 2], val: PlayerCell) -> MoveOutcome {
+        if self.is_paused() {
+            return MoveOutcome::Ignored(IgnoredReason::Paused);
+        }
+        if self.state == GameState::Initial && val != PlayerCell::Revealed {
+            return MoveOutcome::Ignored(IgnoredReason::NotStarted);
+        }
+        let before = self.snapshot();
+        self.apply(ind, val);
+        self.record(before)
+    }
+
+    /// Returns the neighbors that chording on the cell would reveal, or `None`
+    /// if the cell is not a revealed number having as many flagged neighbors
+    /// as its value. Does not modify the board.
+    pub fn preview_chord(&self, x: usize, y: usize) -> Option<Vec<(usize, usize)>> {
         if let GameState::Alive = self.state {
-            // If we try to place a flag while we are at the right number of
-            // bombs, do nothing.
-            if let PlayerCell::Flagged = val {
-                if self.flagged >= self.bombs {
-                    return;
+            let cell = self.get_cell(x, y);
+            if let (PlayerCell::Revealed, CellContent::Nothing(n)) = (cell.player, cell.content) {
+                if self.neighbor_summary(x, y).flagged == n as usize {
+                    return Some(self.neighbors(x, y).filter(|&(nx, ny)| self.is_unknown(nx, ny)).collect());
                 }
             }
+        }
+        None
+    }
 
-            let cell = self.get_mut_cell(ind[0], ind[1]);
-            // If the cell is Revealed, nothing to do.
-            if let PlayerCell::Revealed = cell.player {
+    /// Reveals all the neighbors of a revealed number that are neither
+    /// revealed nor flagged, if it has as many flagged neighbors as its value.
+    /// This is undone as a single move.
+    pub fn chord(&mut self, x: usize, y: usize) -> MoveOutcome {
+        let outcome = self.chord_unlogged(x, y);
+        self.log([x, y], MoveAction::Chord, outcome.clone());
+        outcome
+    }
+
+    /// Same as `chord`, without adding the move to the event log.
+    fn chord_unlogged(&mut self, x: usize, y: usize) -> MoveOutcome {
+        if self.is_paused() {
+            return MoveOutcome::Ignored(IgnoredReason::Paused);
+        }
+        match self.preview_chord(x, y) {
+            Some(_) => self.reveal_neighbors_unlogged(x, y),
+            None => MoveOutcome::Ignored(IgnoredReason::NoEffect),
+        }
+    }
+
+    /// Reveals all the neighbors of a cell that are neither revealed nor
+    /// flagged, whatever the cell is, as a single move. Each reveal goes
+    /// through the usual rules: empty cells cascade and a bomb loses the
+    /// game. `chord` checks the flags around the number first.
+    pub fn reveal_neighbors(&mut self, col: usize, row: usize) -> MoveOutcome {
+        let outcome = self.reveal_neighbors_unlogged(col, row);
+        self.log([col, row], MoveAction::RevealNeighbors, outcome.clone());
+        outcome
+    }
+
+    /// Same as `reveal_neighbors`, without adding the move to the event log.
+    fn reveal_neighbors_unlogged(&mut self, col: usize, row: usize) -> MoveOutcome {
+        if self.is_paused() {
+            return MoveOutcome::Ignored(IgnoredReason::Paused);
+        }
+        if let GameState::Initial = self.state {
+            return MoveOutcome::Ignored(IgnoredReason::NotStarted);
+        }
+        let before = self.snapshot();
+        let cells: Vec<(usize, usize)> = self.neighbors(col, row)
+            .filter(|&(nx, ny)| self.is_unknown(nx, ny))
+            .collect();
+        for (nx, ny) in cells {
+            self.apply([nx, ny], PlayerCell::Revealed);
+        }
+        self.record(before)
+    }
+
+    /// Adds a move to the event log.
+    fn log(&mut self, ind: [usize; 2], action: MoveAction, outcome: MoveOutcome) {
+        self.events.push(MoveEvent {
+            time: self.elapsed(),
+            x: ind[0],
+            y: ind[1],
+            action,
+            outcome,
+        });
+    }
+
+    /// Gets the moves played since the board was created, in order,
+    /// including ignored and undone ones, as well as undos and restarts.
+    pub fn events(&self) -> &[MoveEvent] {
+        &self.events
+    }
+
+    /// Whether every change of the board since it was created is a move of
+    /// the event log, so that the log can be played again from the seed.
+    /// Changes such as `apply_diff` or `expand` are not moves.
+    pub fn is_fully_logged(&self) -> bool {
+        !self.unlogged_changes
+    }
+
+    /// Gets the number of mercy moves the game started with, i.e., the ones
+    /// left and the ones used.
+    pub fn initial_mercy_moves(&self) -> usize {
+        self.mercy_moves + self.mercy_used
+    }
+
+    /// Returns the mean time between consecutive reveals of the player in
+    /// the event log, e.g., to spot hesitations. Only the applied reveals
+    /// count: neither the cells revealed by cascades nor chords. Returns
+    /// `None` if there are fewer than two such reveals.
+    pub fn average_time_between_reveals(&self) -> Option<Duration> {
+        let times: Vec<Duration> = self.events.iter()
+            .filter(|event| {
+                event.action == MoveAction::Set(PlayerCell::Revealed)
+                    && matches!(event.outcome, MoveOutcome::Applied { .. })
+            })
+            .map(|event| event.time)
+            .collect();
+        if times.len() < 2 {
+            return None;
+        }
+        let total = times[times.len() - 1] - times[0];
+        Some(total / (times.len() - 1) as u32)
+    }
+
+    /// Writes the event log as CSV, with a header line, one move per line
+    /// and the time in seconds since the first reveal.
+    pub fn export_csv(&self) -> String {
+        let mut res = String::from("time,x,y,action,outcome\n");
+        for event in self.events.iter() {
+            let action = match event.action {
+                MoveAction::Set(PlayerCell::Revealed) => "reveal",
+                MoveAction::Set(PlayerCell::Flagged) => "flag",
+                MoveAction::Set(PlayerCell::Question) => "question",
+                MoveAction::Set(PlayerCell::Suspected) => "suspect",
+                MoveAction::Set(PlayerCell::NotDetermined) => "clear",
+                MoveAction::Chord => "chord",
+                MoveAction::ContinueCascade => "continue",
+                MoveAction::RevealNeighbors => "reveal neighbors",
+                MoveAction::OpenZeroRegions => "open zero regions",
+                MoveAction::FlagObviousMines => "flag obvious mines",
+                MoveAction::ClearQuestions => "clear questions",
+                MoveAction::Undo => "undo",
+                MoveAction::Redo => "redo",
+                MoveAction::Restart => "restart",
+                MoveAction::RevealAll => "reveal all",
+            };
+            let outcome = match event.outcome {
+                MoveOutcome::Applied { .. } => "applied",
+                MoveOutcome::Ignored(IgnoredReason::Paused) => "paused",
+                MoveOutcome::Ignored(IgnoredReason::NotStarted) => "not started",
+                MoveOutcome::Ignored(IgnoredReason::NoEffect) => "no effect",
+            };
+            res.push_str(&format!(
+                "{:.3},{},{},{},{}\n",
+                event.time.as_secs_f64(), event.x, event.y, action, outcome,
+            ));
+        }
+        res
+    }
+
+    /// Records a move if it changed the board since `before`.
+    fn record(&mut self, before: Snapshot) -> MoveOutcome {
+        let outcome = self.outcome_since(&before);
+        if let MoveOutcome::Applied { pending, .. } = &outcome {
+            self.history.push(before);
+            self.redo_stack.clear();
+            self.cascade_move = None;
+            if !pending.is_empty() {
+                self.last_pending = pending.clone();
+                self.cascade_move = Some(self.history.len());
+            }
+        }
+        outcome
+    }
+
+    /// Tells whether the board changed since `before`, and which cells did,
+    /// without recording it.
+    fn outcome_since(&mut self, before: &Snapshot) -> MoveOutcome {
+        let pending = std::mem::take(&mut self.pending_cascade);
+        if self.cells != before.cells || self.state != before.state {
+            let changed = (0..self.cells.len())
+                .filter(|&i| {
+                    Self::cell_char_and_colors(before.state, &before.cells[i])
+                        != Self::cell_char_and_colors(self.state, &self.cells[i])
+                })
+                .map(|i| (i % self.size[0], i / self.size[0]))
+                .collect();
+            MoveOutcome::Applied { pending, changed }
+        } else {
+            MoveOutcome::Ignored(IgnoredReason::NoEffect)
+        }
+    }
+
+    /// Captures the state of the board.
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            cells: self.cells.clone(),
+            flagged: self.flagged,
+            state: self.state,
+            guesses: self.guesses,
+            started_at: self.started_at,
+            ended_at: self.ended_at,
+            paused_at: self.paused_at,
+            paused_total: self.paused_total,
+            mercy_moves: self.mercy_moves,
+            mercy_used: self.mercy_used,
+        }
+    }
+
+    /// Restores a state of the board, returning the replaced one.
+    fn restore(&mut self, snapshot: Snapshot) -> Snapshot {
+        let current = self.snapshot();
+        self.cells = snapshot.cells;
+        self.flagged = snapshot.flagged;
+        self.state = snapshot.state;
+        self.guesses = snapshot.guesses;
+        self.started_at = snapshot.started_at;
+        self.ended_at = snapshot.ended_at;
+        self.paused_at = snapshot.paused_at;
+        self.paused_total = snapshot.paused_total;
+        self.mercy_moves = snapshot.mercy_moves;
+        self.mercy_used = snapshot.mercy_used;
+        current
+    }
+
+    /// Undoes the last move. Returns `false` if there is no move to undo.
+    pub fn undo(&mut self) -> bool {
+        match self.history.pop() {
+            Some(snapshot) => {
+                let current = self.restore(snapshot);
+                let outcome = self.outcome_since(&current);
+                self.redo_stack.push(current);
+                self.log([0, 0], MoveAction::Undo, outcome);
+                true
+            },
+            None => {
+                self.log([0, 0], MoveAction::Undo, MoveOutcome::Ignored(IgnoredReason::NoEffect));
+                false
+            },
+        }
+    }
+
+    /// Redoes the last undone move. Returns `false` if there is no move to
+    /// redo.
+    pub fn redo(&mut self) -> bool {
+        match self.redo_stack.pop() {
+            Some(snapshot) => {
+                let current = self.restore(snapshot);
+                let outcome = self.outcome_since(&current);
+                self.history.push(current);
+                self.log([0, 0], MoveAction::Redo, outcome);
+                true
+            },
+            None => {
+                self.log([0, 0], MoveAction::Redo, MoveOutcome::Ignored(IgnoredReason::NoEffect));
+                false
+            },
+        }
+    }
+
+    /// Undoes the move that lost the game, e.g., after a misclick. Returns
+    /// `false` if the game is not lost.
+    pub fn rewind_to_before_loss(&mut self) -> bool {
+        self.state == GameState::Lost && self.undo()
+    }
+
+    /// Applies the player input, without recording it.
+    fn apply(&mut self, ind: [usize; 2], val: PlayerCell) {
+        if let GameState::Initial = self.state {
+            // If the game is in Initial state and the value is not a Revealed
+            // one, do nothing.
+            if let PlayerCell::Revealed = val {
+                // Record that we revealed a cell, and then determine the
+                // bomb positions.
+                let cell = self.get_mut_cell(ind[0], ind[1]);
+                cell.set_player(PlayerCell::Revealed);
+                self.init(ind);
+                // Only perform the optimization if the player has some luck.
+                if let CellContent::Nothing(0) = self.get_cell(ind[0], ind[1]).content {
+                    self.reveal_with_no_neighbors(ind[0], ind[1]);
+                }
+            }
+            return;
+        }
+
+        // We can only set something if we are in the Alive state.
+        if let GameState::Alive = self.state {
+            // If we try to place a flag while we are at the right number of
+            // bombs, do nothing.
+            if let PlayerCell::Flagged = val {
+                if self.restrict_flags && self.flagged >= self.bombs {
+                    return;
+                }
+            }
+
+            // If the cell is Revealed, nothing to do.
+            if let PlayerCell::Revealed = self.get_cell(ind[0], ind[1]).player {
                 return;
             }
 
+            // Was the player sure that the cell is safe?
+            if let (PlayerCell::Revealed, true) = (val, self.track_guesses) {
+                if !self.is_provably_safe(ind[0], ind[1]) {
+                    self.guesses += 1;
+                }
+            }
+
+            // If the player is about to reveal a bomb, forgive it if some
+            // mercy is left.
+            if let (PlayerCell::Revealed, CellContent::Bomb) = (val, self.get_cell(ind[0], ind[1]).content) {
+                if self.mercy_moves > 0 && self.relocate_bomb(ind) {
+                    self.mercy_moves -= 1;
+                    self.mercy_used += 1;
+                }
+            }
+
             // Ok, then something should probably be set.
-            cell.player = val;
+            let cell = self.get_mut_cell(ind[0], ind[1]);
+            cell.set_player(val);
 
             // Add the optimization to reduce the number of clicks.
             if let PlayerCell::Revealed = val {
-                match cell.content {
-                    CellContent::Nothing(0) => self.reveal_with_no_neighbors(ind[0], ind[1]),
-                    _ => {},
+                if let CellContent::Nothing(0) = cell.content {
+                    self.reveal_with_no_neighbors(ind[0], ind[1]);
                 }
             }
 
@@ -306,46 +1592,2558 @@ impl Gameboard {
         }
     }
 
-    /// Gets the character with its own font and background color at cell location.
-    /// TODO: pictures.
-    pub fn char_and_colors(&self, ind: [usize; 2]) -> (Option<(char, Color)>, Color) {
-        let cell = self.get_cell(ind[0], ind[1]);
-        match self.state {
-            GameState::Lost => {
-                // If we lost, reveal the bomb positions.
-                match cell.content {
-                    CellContent::Nothing(_) => return (None, ND_BACKGROUND),
-                    CellContent::Bomb => (Some(('B', BLACK)), BOMB_BACKGROUND),
+    /// Whether the player has no information about the cell, i.e., it is
+    /// neither revealed nor flagged.
+    fn is_unknown(&self, x: usize, y: usize) -> bool {
+        match self.get_cell(x, y).player {
+            PlayerCell::NotDetermined | PlayerCell::Question | PlayerCell::Suspected => true,
+            PlayerCell::Flagged | PlayerCell::Revealed => false,
+        }
+    }
+
+    /// Whether the cell is a revealed number having at least one unknown
+    /// neighbor.
+    fn is_frontier(&self, x: usize, y: usize) -> bool {
+        let cell = self.get_cell(x, y);
+        if let (PlayerCell::Revealed, CellContent::Nothing(_)) = (cell.player, cell.content) {
+            for (nx, ny) in self.neighbors(x, y) {
+                if self.is_unknown(nx, ny) {
+                    return true;
                 }
-            },
-            _ => {
-                // In other states, show the player input.
-                match cell.player {
-                    PlayerCell::NotDetermined => (None, ND_BACKGROUND),
-                    PlayerCell::Flagged => (Some(('F', BLACK)), FLAGGED_BACKGROUND),
-                    PlayerCell::Question => (Some(('?', BLACK)), ND_BACKGROUND),
-                    PlayerCell::Revealed => {
-                        // If we reveal the input, we should only have nothing
-                        // in the cell.
-                        match cell.content {
-                            CellContent::Nothing(v) => match v {
-                                0 => (None, REV_BACKGROUND),
-                                1 => (Some(('1', ONE_COLOR)), REV_BACKGROUND),
-                                2 => (Some(('2', TWO_COLOR)), REV_BACKGROUND),
-                                3 => (Some(('3', THREE_COLOR)), REV_BACKGROUND),
-                                4 => (Some(('4', FOUR_COLOR)), REV_BACKGROUND),
-                                5 => (Some(('5', FIVE_COLOR)), REV_BACKGROUND),
-                                6 => (Some(('6', SIX_COLOR)), REV_BACKGROUND),
-                                7 => (Some(('7', SEVEN_COLOR)), REV_BACKGROUND),
-                                8 => (Some(('8', EIGTH_COLOR)), REV_BACKGROUND),
-                                // Not possible to have more than 8
-                                _ => panic!("more than 8 bombs???"),
-                            },
-                            _ => (None, REV_BACKGROUND),
+            }
+        }
+        false
+    }
+
+    /// Returns the revealed numbered cells having at least one neighbor that
+    /// is neither revealed nor flagged. These are the cells giving constraints
+    /// about the unknown part of the board.
+    pub fn frontier(&self) -> Vec<(usize, usize)> {
+        let mut res = Vec::new();
+        for y in 0..self.size[1] {
+            for x in 0..self.size[0] {
+                if self.is_frontier(x, y) {
+                    res.push((x, y));
+                }
+            }
+        }
+        res
+    }
+
+    /// Counts the regions of cells neither revealed nor flagged that are not
+    /// orthogonally adjacent to each other, e.g., to find isolated areas in
+    /// the endgame.
+    pub fn unrevealed_island_count(&self) -> usize {
+        let mut visited = vec![false; self.cells.len()];
+        let mut res = 0;
+        for y in 0..self.size[1] {
+            for x in 0..self.size[0] {
+                if visited[self.idx(x, y)] || !self.is_unknown(x, y) {
+                    continue;
+                }
+                res += 1;
+                visited[self.idx(x, y)] = true;
+                let mut to_visit = vec![(x, y)];
+                while let Some((x, y)) = to_visit.pop() {
+                    // Out of the board coordinates wrap to large values.
+                    let next = [(x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)];
+                    for &(nx, ny) in next.iter().filter(|&&(nx, ny)| nx < self.size[0] && ny < self.size[1]) {
+                        let idx = self.idx(nx, ny);
+                        if !visited[idx] && self.is_unknown(nx, ny) {
+                            visited[idx] = true;
+                            to_visit.push((nx, ny));
                         }
                     }
                 }
             }
         }
+        res
+    }
+
+    /// Returns the constraints given by the frontier, e.g., for an external
+    /// SAT or ILP solver: for each number, its neighbors neither revealed
+    /// nor flagged and how many of them have a bomb, i.e., the number minus
+    /// its flagged neighbors. Flags are trusted.
+    pub fn export_constraints(&self) -> Vec<(Vec<(usize, usize)>, u8)> {
+        self.frontier().into_iter()
+            .filter_map(|(x, y)| {
+                let number = match self.get_cell(x, y).content {
+                    CellContent::Nothing(n) => n,
+                    CellContent::Bomb => return None,
+                };
+                let cells: Vec<(usize, usize)> = self.neighbors(x, y)
+                    .filter(|&(nx, ny)| self.is_unknown(nx, ny))
+                    .collect();
+                let flagged = self.neighbor_summary(x, y).flagged;
+                Some((cells, number.saturating_sub(flagged as u8)))
+            })
+            .collect()
+    }
+
+    /// Returns the cells neither revealed nor flagged that are adjacent to
+    /// the frontier, i.e., the unknown cells constrained by a revealed number.
+    pub fn unknown_frontier(&self) -> Vec<(usize, usize)> {
+        let mut res = Vec::new();
+        for y in 0..self.size[1] {
+            for x in 0..self.size[0] {
+                if !self.is_unknown(x, y) {
+                    continue;
+                }
+                if self.neighbors(x, y).any(|(nx, ny)| self.is_frontier(nx, ny)) {
+                    res.push((x, y));
+                }
+            }
+        }
+        res
+    }
+
+    /// Builds the independent groups of constraints given by the frontier.
+    fn constraint_groups(&self) -> Vec<ConstraintGroup> {
+        let unknown = self.unknown_frontier();
+        let mut index = vec![None; self.cells.len()];
+        for (i, &(x, y)) in unknown.iter().enumerate() {
+            index[self.idx(x, y)] = Some(i);
+        }
+
+        // Gather the constraints, with cells indexed in `unknown`.
+        let mut constraints = Vec::new();
+        for (x, y) in self.frontier() {
+            let n = match self.get_cell(x, y).content {
+                CellContent::Nothing(n) => n as usize,
+                CellContent::Bomb => continue,
+            };
+            let mut cells = Vec::new();
+            let mut flags = 0;
+            for (nx, ny) in self.neighbors(x, y) {
+                match self.get_cell(nx, ny).player {
+                    PlayerCell::Flagged => flags += 1,
+                    _ => if let Some(i) = index[self.idx(nx, ny)] {
+                        cells.push(i);
+                    },
+                }
+            }
+            // If the player placed too many flags around, the constraint
+            // cannot be trusted.
+            if let Some(mines) = n.checked_sub(flags) {
+                constraints.push(Constraint { cells, mines });
+            }
+        }
+
+        // Link cells sharing a constraint, using a simple union-find.
+        fn root(parent: &mut [usize], mut i: usize) -> usize {
+            while parent[i] != i {
+                parent[i] = parent[parent[i]];
+                i = parent[i];
+            }
+            i
+        }
+        let mut parent: Vec<usize> = (0..unknown.len()).collect();
+        for constraint in &constraints {
+            for &c in constraint.cells.iter().skip(1) {
+                let a = root(&mut parent, constraint.cells[0]);
+                let b = root(&mut parent, c);
+                parent[a] = b;
+            }
+        }
+
+        // Then split cells and constraints by group, reindexing cells.
+        let mut group_of_root = vec![None; unknown.len()];
+        let mut local_index = vec![0; unknown.len()];
+        let mut groups: Vec<ConstraintGroup> = Vec::new();
+        for (i, &pos) in unknown.iter().enumerate() {
+            let r = root(&mut parent, i);
+            let g = *group_of_root[r].get_or_insert_with(|| {
+                groups.push(ConstraintGroup { cells: Vec::new(), constraints: Vec::new() });
+                groups.len() - 1
+            });
+            local_index[i] = groups[g].cells.len();
+            groups[g].cells.push(pos);
+        }
+        for constraint in constraints {
+            if constraint.cells.is_empty() {
+                continue;
+            }
+            let g = group_of_root[root(&mut parent, constraint.cells[0])].unwrap();
+            groups[g].constraints.push(Constraint {
+                cells: constraint.cells.iter().map(|&i| local_index[i]).collect(),
+                mines: constraint.mines,
+            });
+        }
+        groups
+    }
+
+    /// Calls `f` with every mine assignment of the group cells satisfying all
+    /// its constraints, `true` meaning a mine. Returns `false` without
+    /// enumerating anything if the group exceeds `CSP_MAX_GROUP_SIZE`.
+    fn for_each_solution<F: FnMut(&[bool])>(group: &ConstraintGroup, f: &mut F) -> bool {
+        if group.cells.len() > CSP_MAX_GROUP_SIZE {
+            return false;
+        }
+        let mut cell_constraints = vec![Vec::new(); group.cells.len()];
+        let mut left = vec![0; group.constraints.len()];
+        for (c, constraint) in group.constraints.iter().enumerate() {
+            for &i in &constraint.cells {
+                cell_constraints[i].push(c);
+            }
+            left[c] = constraint.cells.len();
+        }
+        let mut mines = vec![0; group.constraints.len()];
+        let mut assignment = vec![false; group.cells.len()];
+
+        fn enumerate<F: FnMut(&[bool])>(
+            group: &ConstraintGroup,
+            cell_constraints: &[Vec<usize>],
+            assignment: &mut Vec<bool>,
+            mines: &mut Vec<usize>,
+            left: &mut Vec<usize>,
+            i: usize,
+            f: &mut F,
+        ) {
+            if i == assignment.len() {
+                f(assignment);
+                return;
+            }
+            for &is_mine in &[false, true] {
+                let mut ok = true;
+                for &c in &cell_constraints[i] {
+                    left[c] -= 1;
+                    if is_mine {
+                        mines[c] += 1;
+                    }
+                    let need = group.constraints[c].mines;
+                    if mines[c] > need || mines[c] + left[c] < need {
+                        ok = false;
+                    }
+                }
+                if ok {
+                    assignment[i] = is_mine;
+                    enumerate(group, cell_constraints, assignment, mines, left, i + 1, f);
+                }
+                for &c in &cell_constraints[i] {
+                    left[c] += 1;
+                    if is_mine {
+                        mines[c] -= 1;
+                    }
+                }
+            }
+        }
+
+        enumerate(group, &cell_constraints, &mut assignment, &mut mines, &mut left, 0, f);
+        true
+    }
+
+    /// Returns the number of mine assignments satisfying the group
+    /// constraints and, for each cell of the group, in how many of them it is
+    /// a mine. Returns `None` if the group is too large to be enumerated.
+    fn group_mine_counts(group: &ConstraintGroup) -> Option<(usize, Vec<usize>)> {
+        let mut solutions = 0;
+        let mut mine_counts = vec![0; group.cells.len()];
+        let enumerated = Self::for_each_solution(group, &mut |assignment| {
+            solutions += 1;
+            for (count, &is_mine) in mine_counts.iter_mut().zip(assignment) {
+                if is_mine {
+                    *count += 1;
+                }
+            }
+        });
+        if enumerated {
+            Some((solutions, mine_counts))
+        } else {
+            None
+        }
+    }
+
+    /// Splits a group too large to be enumerated into windows, one per
+    /// constraint, holding it with the constraints sharing cells with it as
+    /// long as the window has at most `CSP_MAX_GROUP_SIZE` cells. A window
+    /// only drops constraints of the group, so what holds in all its
+    /// solutions holds in all the solutions of the group.
+    fn local_windows(group: &ConstraintGroup) -> Vec<ConstraintGroup> {
+        let mut res = Vec::new();
+        for (c, constraint) in group.constraints.iter().enumerate() {
+            let overlapping = (0..group.constraints.len())
+                .filter(|&o| o != c && group.constraints[o].cells.iter().any(|i| constraint.cells.contains(i)));
+            let mut local_index = vec![None; group.cells.len()];
+            let mut window = ConstraintGroup { cells: Vec::new(), constraints: Vec::new() };
+            for o in std::iter::once(c).chain(overlapping) {
+                let other = &group.constraints[o];
+                let new_cells = other.cells.iter().filter(|&&i| local_index[i].is_none()).count();
+                if window.cells.len() + new_cells > CSP_MAX_GROUP_SIZE {
+                    continue;
+                }
+                for &i in &other.cells {
+                    if local_index[i].is_none() {
+                        local_index[i] = Some(window.cells.len());
+                        window.cells.push(group.cells[i]);
+                    }
+                }
+                window.constraints.push(Constraint {
+                    cells: other.cells.iter().map(|&i| local_index[i].unwrap()).collect(),
+                    mines: other.mines,
+                });
+            }
+            res.push(window);
+        }
+        res
+    }
+
+    /// Returns the cells of the group that are a mine (`true`) or safe
+    /// (`false`) in all the `solutions`, given how many of them have a mine
+    /// on each cell.
+    fn certain_cells(group: &ConstraintGroup, solutions: usize, mine_counts: &[usize]) -> Vec<((usize, usize), bool)> {
+        if solutions == 0 {
+            return Vec::new();
+        }
+        group.cells.iter().zip(mine_counts)
+            .filter_map(|(&pos, &count)| match count {
+                0 => Some((pos, false)),
+                _ if count == solutions => Some((pos, true)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the cells of the group that are a mine (`true`) or safe
+    /// (`false`) in every mine assignment satisfying its constraints. Groups
+    /// too large to be enumerated are solved window by window, which finds
+    /// the deductions combining a number with its neighboring ones.
+    fn group_deductions(group: &ConstraintGroup) -> Vec<((usize, usize), bool)> {
+        if let Some((solutions, mine_counts)) = Self::group_mine_counts(group) {
+            return Self::certain_cells(group, solutions, &mine_counts);
+        }
+        let mut res = Vec::new();
+        for window in Self::local_windows(group) {
+            if let Some((solutions, mine_counts)) = Self::group_mine_counts(&window) {
+                for deduction in Self::certain_cells(&window, solutions, &mine_counts) {
+                    if !res.contains(&deduction) {
+                        res.push(deduction);
+                    }
+                }
+            }
+        }
+        res
+    }
+
+    /// Returns the unknown cells that are a mine (`true`) or safe (`false`)
+    /// in every mine assignment consistent with the revealed numbers. The
+    /// total number of bombs is not taken into account.
+    fn csp_deductions(&self) -> Vec<((usize, usize), bool)> {
+        self.constraint_groups().iter()
+            .flat_map(Self::group_deductions)
+            .collect()
+    }
+
+    /// Returns a lower bound of the number of mines among the cells of
+    /// `region` that are neither revealed nor flagged, deduced from the
+    /// revealed numbers. Groups too large to be enumerated only contribute
+    /// the best bound given by a single number.
+    pub fn min_remaining_bombs_in(&self, region: &[(usize, usize)]) -> usize {
+        let mut res = 0;
+        for group in self.constraint_groups() {
+            let in_region: Vec<bool> = group.cells.iter().map(|pos| region.contains(pos)).collect();
+            let mut min_mines = None;
+            let enumerated = Self::for_each_solution(&group, &mut |assignment| {
+                let mines = assignment.iter().zip(&in_region).filter(|(&m, &r)| m && r).count();
+                min_mines = Some(min_mines.map_or(mines, |min_mines: usize| min_mines.min(mines)));
+            });
+            res += if enumerated {
+                min_mines.unwrap_or(0)
+            } else {
+                group.constraints.iter()
+                    .map(|c| c.mines.saturating_sub(c.cells.iter().filter(|&&i| !in_region[i]).count()))
+                    .max()
+                    .unwrap_or(0)
+            };
+        }
+        res
+    }
+
+    /// Returns a cell that the revealed numbers prove to be a mine (`true`)
+    /// or safe (`false`), if any.
+    pub fn hint(&self) -> Option<((usize, usize), bool)> {
+        self.csp_deductions().into_iter().next()
+    }
+
+    /// Tells whether the revealed numbers prove that the cell, neither
+    /// revealed nor flagged, is safe or a mine, using the same solver as
+    /// `hint`. Returns `None` if it cannot be determined.
+    pub fn deduction_status(&self, x: usize, y: usize) -> Option<Deduced> {
+        if !self.is_unknown(x, y) {
+            return None;
+        }
+        self.csp_deductions().into_iter()
+            .find(|&(cell, _)| cell == (x, y))
+            .map(|(_, is_mine)| if is_mine { Deduced::Mine } else { Deduced::Safe })
+    }
+
+    /// Counts the cells neither revealed nor flagged that the revealed
+    /// numbers prove to be mines, using the same solver as `hint`.
+    pub fn proven_mine_count(&self) -> usize {
+        self.csp_deductions().iter().filter(|&&(_, is_mine)| is_mine).count()
+    }
+
+    /// Returns the cell proven safe by the revealed numbers, using the same
+    /// solver as `hint`, that is the closest to `from` in Manhattan distance,
+    /// e.g., to guide a lost beginner. Returns `None` if no cell is proven
+    /// safe.
+    pub fn nearest_safe_cell(&self, from: (usize, usize)) -> Option<(usize, usize)> {
+        self.csp_deductions().into_iter()
+            .filter(|&(_, is_mine)| !is_mine)
+            .map(|(cell, _)| cell)
+            .min_by_key(|&(x, y)| x.abs_diff(from.0) + y.abs_diff(from.1))
+    }
+
+    /// Returns the deductions that follow from a single revealed number:
+    /// its unknown neighbors are all safe if its flagged neighbors account
+    /// for the number, or all mines if they are exactly the missing ones.
+    /// Each target cell is explained once. Deductions combining several
+    /// numbers, found by `hint`, are not explained.
+    pub fn hint_explained(&self) -> Vec<Deduction> {
+        let mut res: Vec<Deduction> = Vec::new();
+        for (x, y) in self.frontier() {
+            let number = match self.get_cell(x, y).content {
+                CellContent::Nothing(n) => n,
+                CellContent::Bomb => continue,
+            };
+            let summary = self.neighbor_summary(x, y);
+            let mine = match (number as usize).checked_sub(summary.flagged) {
+                Some(0) => false,
+                Some(missing) if missing == summary.unrevealed => true,
+                _ => continue,
+            };
+            for target in self.neighbors(x, y).filter(|&(nx, ny)| self.is_unknown(nx, ny)) {
+                if res.iter().all(|d| d.target != target) {
+                    res.push(Deduction { source: (x, y), number, target, mine });
+                }
+            }
+        }
+        res
+    }
+
+    /// Whether the revealed numbers prove that the cell is safe.
+    fn is_provably_safe(&self, x: usize, y: usize) -> bool {
+        self.csp_deductions().contains(&((x, y), false))
+    }
+
+    /// Returns the number of reveals the player made without the revealed
+    /// numbers proving that the cell was safe, while `track_guesses` was on.
+    /// The first reveal is never a guess, as it cannot hit a bomb.
+    pub fn guess_count(&self) -> usize {
+        self.guesses
+    }
+
+    /// Estimates the probability that each cell neither revealed nor flagged
+    /// has a bomb. Cells next to revealed numbers get the fraction of the
+    /// mine assignments of their group having a bomb there, assignments
+    /// being equally likely, or 0.0 and 1.0 for the cells proven by the
+    /// windows of groups too large to be enumerated. The bombs left are
+    /// spread evenly on the other cells.
+    fn mine_probabilities(&self) -> Vec<((usize, usize), f64)> {
+        let mut res = Vec::new();
+        let mut expected_mines = 0.0;
+        for group in self.constraint_groups() {
+            match Self::group_mine_counts(&group) {
+                Some((solutions, mine_counts)) => if solutions > 0 {
+                    for (&pos, &count) in group.cells.iter().zip(&mine_counts) {
+                        let p = count as f64 / solutions as f64;
+                        expected_mines += p;
+                        res.push((pos, p));
+                    }
+                },
+                // Only the cells proven by windows of the group are known.
+                None => for (pos, is_mine) in Self::group_deductions(&group) {
+                    let p = if is_mine { 1.0 } else { 0.0 };
+                    expected_mines += p;
+                    res.push((pos, p));
+                },
+            }
+        }
+
+        let mut others = Vec::new();
+        for y in 0..self.size[1] {
+            for x in 0..self.size[0] {
+                if self.is_unknown(x, y) && res.iter().all(|&(pos, _)| pos != (x, y)) {
+                    others.push((x, y));
+                }
+            }
+        }
+        let left = self.bombs as f64 - self.count_total_flags() as f64 - expected_mines;
+        let density = (left / others.len() as f64).clamp(0.0, 1.0);
+        res.extend(others.into_iter().map(|pos| (pos, density)));
+        res
+    }
+
+    /// Estimates the probability of winning the game, 1.0 if a cell is
+    /// proven safe or the bombs are not placed yet. Otherwise, this is a
+    /// greedy estimate: a solver plays the deductions of `csp_step` on a
+    /// copy of the board and, when stuck, reveals the cell the least likely
+    /// to have a bomb, whose probability of being safe multiplies the
+    /// estimate. The solver follows the actual layout, and stops at the
+    /// first guess hitting a bomb.
+    pub fn win_probability(&self) -> f64 {
+        match self.state {
+            GameState::Initial | GameState::Won => return 1.0,
+            GameState::Lost => return 0.0,
+            GameState::Alive => {},
+        }
+        if self.csp_deductions().iter().any(|&(_, is_mine)| !is_mine) {
+            return 1.0;
+        }
+
+        let mut board = self.solver_clone();
+        let mut res = 1.0;
+        while board.state == GameState::Alive {
+            if board.csp_step() {
+                continue;
+            }
+            let guess = board.mine_probabilities().into_iter()
+                .min_by(|(_, p1), (_, p2)| p1.partial_cmp(p2).unwrap());
+            let ((x, y), p) = match guess {
+                Some(guess) => guess,
+                // Only flags are left, but some are wrong.
+                None => return 0.0,
+            };
+            res *= 1.0 - p;
+            if let CellContent::Bomb = board.get_cell(x, y).content {
+                break;
+            }
+            board.set([x, y], PlayerCell::Revealed);
+        }
+        res
+    }
+
+    /// Estimates the fraction of games won by a player revealing
+    /// `first_click`, playing the deductions of `csp_step` and revealing a
+    /// random cell when stuck, over `trials` games on copies of the board,
+    /// e.g., to rate boards. If the bombs are not placed yet, each game gets
+    /// its own layout. The games are determined by `seed`. Returns 0.0 if
+    /// `trials` is zero.
+    pub fn monte_carlo_winrate(&self, first_click: (usize, usize), trials: usize, seed: u64) -> f64 {
+        if trials == 0 {
+            return 0.0;
+        }
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut wins = 0;
+        for _ in 0..trials {
+            let mut board = self.solver_clone();
+            // Bombs enclosed by other bombs cannot be deduced.
+            board.auto_flag_on_clear = true;
+            board.set_seed(rng.gen());
+            if board.is_unknown(first_click.0, first_click.1) {
+                board.set([first_click.0, first_click.1], PlayerCell::Revealed);
+            }
+            while board.state == GameState::Alive {
+                if board.csp_step() {
+                    continue;
+                }
+                let unknown: Vec<(usize, usize)> = (0..board.size[1])
+                    .flat_map(|y| (0..board.size[0]).map(move |x| (x, y)))
+                    .filter(|&(x, y)| board.is_unknown(x, y))
+                    .collect();
+                if unknown.is_empty() {
+                    // Only flags are left, but some are wrong.
+                    break;
+                }
+                let (x, y) = unknown[rng.gen_range(0..unknown.len())];
+                board.set([x, y], PlayerCell::Revealed);
+            }
+            if let GameState::Won = board.state {
+                wins += 1;
+            }
+        }
+        wins as f64 / trials as f64
+    }
+
+    /// Performs one step of constraint-satisfaction solving: flags the cells
+    /// that are mines and reveals the ones that are safe in every mine
+    /// assignment consistent with the revealed numbers. Deductions combining
+    /// several numbers are thus found. Returns whether progress was made.
+    pub fn csp_step(&mut self) -> bool {
+        if let GameState::Alive = self.state {
+            let mut progress = false;
+            for ((x, y), is_mine) in self.csp_deductions() {
+                let val = if is_mine { PlayerCell::Flagged } else { PlayerCell::Revealed };
+                // A previous reveal may have already revealed the cell.
+                if !self.is_unknown(x, y) {
+                    continue;
+                }
+                self.set([x, y], val);
+                progress |= !self.is_unknown(x, y);
+            }
+            progress
+        } else {
+            false
+        }
+    }
+
+    /// Copies the board for the solver to play on: cells and game rules are
+    /// kept, but not the history, the event log or the timers.
+    pub fn solver_clone(&self) -> Gameboard {
+        Gameboard {
+            size: self.size,
+            bombs: self.bombs,
+            flagged: self.flagged,
+            state: self.state,
+            restrict_flags: self.restrict_flags,
+            min_opening: self.min_opening,
+            guaranteed_opening: self.guaranteed_opening,
+            seed: self.seed,
+            total_safe: self.total_safe,
+            diagonal_cascade: self.diagonal_cascade,
+            auto_flag_on_clear: self.auto_flag_on_clear,
+            cells: self.cells.clone(),
+            ..Gameboard::new([1, 1], 0)
+        }
     }
-}
\ No newline at end of file
+
+    /// Whether solving the board from `first_click` with the
+    /// constraint-satisfaction solver gets stuck on a cell being a mine with
+    /// a probability of exactly one half, i.e., the player has to guess. If
+    /// the board is in the `Initial` state, revealing `first_click` places
+    /// the bombs of the board being analysed, not of `self`.
+    pub fn has_unavoidable_5050(&self, first_click: [usize; 2]) -> bool {
+        let mut board = self.solver_clone();
+        if board.is_unknown(first_click[0], first_click[1]) {
+            board.set(first_click, PlayerCell::Revealed);
+        }
+        while board.csp_step() {}
+        if let GameState::Won | GameState::Lost = board.state {
+            return false;
+        }
+
+        for group in board.constraint_groups() {
+            if let Some((solutions, mine_counts)) = Self::group_mine_counts(&group) {
+                if solutions > 0 && mine_counts.iter().any(|&count| 2 * count == solutions) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Gets the difficulty preset having the size and the number of bombs of
+    /// the board, in either orientation, or `Custom` if there is none.
+    pub fn classify_difficulty(&self) -> Difficulty {
+        let transposed = [self.size[1], self.size[0]];
+        for &difficulty in &[Difficulty::Beginner, Difficulty::Intermediate, Difficulty::Expert] {
+            if let Some((size, bombs)) = difficulty.size_and_bombs() {
+                if (size == self.size || size == transposed) && bombs == self.bombs {
+                    return difficulty;
+                }
+            }
+        }
+        Difficulty::Custom
+    }
+
+    /// Whether the bomb layout has the given symmetry. Always `false` in
+    /// the `Initial` state, as bombs are not placed yet.
+    pub fn is_symmetric(&self, kind: Symmetry) -> bool {
+        if let GameState::Initial = self.state {
+            return false;
+        }
+        let is_bomb = |x: usize, y: usize| match self.get_cell(x, y).content {
+            CellContent::Bomb => true,
+            CellContent::Nothing(_) => false,
+        };
+        let (w, h) = (self.size[0], self.size[1]);
+        for y in 0..h {
+            for x in 0..w {
+                let (mx, my) = match kind {
+                    Symmetry::Horizontal => (x, h - 1 - y),
+                    Symmetry::Vertical => (w - 1 - x, y),
+                    Symmetry::Rotational => (w - 1 - x, h - 1 - y),
+                };
+                if is_bomb(x, y) != is_bomb(mx, my) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    /// Counts the cells currently flagged by the player, which the `flagged`
+    /// field keeps track of after each move.
+    pub fn count_total_flags(&self) -> usize {
+        self.cells.iter().filter(|cell| matches!(cell.player, PlayerCell::Flagged)).count()
+    }
+
+    /// Counts the bombs actually placed on the board, which may be fewer
+    /// than `bombs` if it was set too high for the board.
+    pub fn actual_bomb_count(&self) -> usize {
+        self.cells.iter().filter(|cell| matches!(cell.content, CellContent::Bomb)).count()
+    }
+
+    /// Returns the number of flags on the board, as `count_total_flags`,
+    /// minus the number of bombs: negative if bombs are left to flag,
+    /// positive if the player placed more flags than there are bombs, which
+    /// `restrict_flags` prevents.
+    pub fn flag_balance(&self) -> i64 {
+        self.count_total_flags() as i64 - self.bombs as i64
+    }
+
+    /// Whether the cell is a revealed number having exactly as many flagged
+    /// neighbors as its value.
+    pub fn is_number_satisfied(&self, x: usize, y: usize) -> bool {
+        let cell = self.get_cell(x, y);
+        match (cell.player, cell.content) {
+            (PlayerCell::Revealed, CellContent::Nothing(n)) => self.neighbor_summary(x, y).flagged == n as usize,
+            _ => false,
+        }
+    }
+
+    /// Summarizes what the player knows about the neighbors of a cell, e.g.,
+    /// to show it as a tooltip.
+    pub fn neighbor_summary(&self, x: usize, y: usize) -> NeighborSummary {
+        let mut res = NeighborSummary::default();
+        for (_, _, cell) in self.neighbor_cells(x, y) {
+            match cell.player {
+                PlayerCell::Flagged => res.flagged += 1,
+                PlayerCell::Revealed => res.revealed += 1,
+                PlayerCell::NotDetermined | PlayerCell::Question | PlayerCell::Suspected => res.unrevealed += 1,
+            }
+        }
+        res
+    }
+
+    /// Gets when the game was lost, if it is.
+    pub fn lost_at(&self) -> Option<Instant> {
+        match self.state {
+            GameState::Lost => self.ended_at,
+            _ => None,
+        }
+    }
+
+    /// Returns the positions of the bombs sorted by their distance to the
+    /// fatal cell `from`, nearest first, e.g., to make them explode one after
+    /// the other on loss. Bombs at the same distance are sorted row by row.
+    pub fn bomb_detonation_order(&self, from: (usize, usize)) -> Vec<(usize, usize)> {
+        let mut res: Vec<(usize, usize)> = (0..self.size[1])
+            .flat_map(|y| (0..self.size[0]).map(move |x| (x, y)))
+            .filter(|&(x, y)| self.get_cell(x, y).content == CellContent::Bomb)
+            .collect();
+        res.sort_by_key(|&(x, y)| {
+            let dx = x as i64 - from.0 as i64;
+            let dy = y as i64 - from.1 as i64;
+            dx * dx + dy * dy
+        });
+        res
+    }
+
+    /// Gets the time spent since the first reveal, until the end of the game
+    /// if it is over.
+    pub fn elapsed(&self) -> Duration {
+        let total = match (self.started_at, self.ended_at.or(self.paused_at)) {
+            (Some(started_at), Some(stopped_at)) => stopped_at - started_at,
+            (Some(started_at), None) => started_at.elapsed(),
+            _ => Duration::from_secs(0),
+        };
+        total.saturating_sub(self.paused_total)
+    }
+
+    /// Pauses the game, stopping the timer and ignoring moves until `resume`
+    /// is called. Returns `false` if the game is not `Alive` or is already
+    /// paused.
+    pub fn pause(&mut self) -> bool {
+        if self.state != GameState::Alive || self.is_paused() {
+            return false;
+        }
+        self.paused_at = Some(Instant::now());
+        true
+    }
+
+    /// Resumes a paused game. Returns `false` if it is not paused.
+    pub fn resume(&mut self) -> bool {
+        match self.paused_at.take() {
+            Some(paused_at) => {
+                self.paused_total += paused_at.elapsed();
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Whether the game is paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    /// Whether the game is won.
+    pub fn is_won(&self) -> bool {
+        self.state == GameState::Won
+    }
+
+    /// Whether the game is lost.
+    pub fn is_lost(&self) -> bool {
+        self.state == GameState::Lost
+    }
+
+    /// Whether the game is won or lost.
+    pub fn is_over(&self) -> bool {
+        self.is_won() || self.is_lost()
+    }
+
+    /// Returns the fraction of safe cells revealed by the player, between 0.0
+    /// at the start of a game and 1.0 once the game is won.
+    pub fn progress_fraction(&self) -> f64 {
+        let safe = self.total_safe_cells();
+        (safe - self.cells_left_to_win()) as f64 / safe as f64
+    }
+
+    /// Returns the number of cells without bomb, i.e., the cells revealed
+    /// once the game is won. Before the bombs are placed, it is deduced from
+    /// `bombs`.
+    pub fn total_safe_cells(&self) -> usize {
+        match (self.state, self.total_safe) {
+            (GameState::Initial, _) | (_, None) => self.cells.len() - min(self.bombs, self.cells.len()),
+            (_, Some(total)) => total,
+        }
+    }
+
+    /// Returns the number of safe cells the player still has to reveal.
+    pub fn cells_left_to_win(&self) -> usize {
+        if let GameState::Initial = self.state {
+            // The bombs are not placed yet.
+            return self.total_safe_cells();
+        }
+        self.cells.iter()
+            .filter(|cell| cell.content != CellContent::Bomb && cell.player != PlayerCell::Revealed)
+            .count()
+    }
+
+    /// Applies the moves in order, stopping early when the game ends or at
+    /// the first move out of the board. Returns how many moves were applied.
+    pub fn apply_moves(&mut self, moves: &[(usize, usize, PlayerCell)]) -> usize {
+        let mut applied = 0;
+        for &(x, y, val) in moves {
+            if let GameState::Won | GameState::Lost = self.state {
+                break;
+            }
+            if self.try_set([x, y], val).is_err() {
+                break;
+            }
+            applied += 1;
+        }
+        applied
+    }
+
+    /// Reveals the cells of the rectangle between the two corners, included,
+    /// row by row, e.g., for testing or a cheat mode. Flagged cells are kept.
+    /// The usual rules apply: revealing a bomb loses the game, which stops
+    /// the reveals. Parts of the rectangle out of the board are ignored.
+    pub fn reveal_region(&mut self, col0: usize, row0: usize, col1: usize, row1: usize) {
+        let (x0, x1) = (min(col0, col1), min(col0.max(col1), self.size[0] - 1));
+        let (y0, y1) = (min(row0, row1), min(row0.max(row1), self.size[1] - 1));
+        let mut moves = Vec::new();
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                if self.is_unknown(x, y) {
+                    moves.push((x, y, PlayerCell::Revealed));
+                }
+            }
+        }
+        self.apply_moves(&moves);
+    }
+
+    /// Flags the cell if it is not determined yet, or removes its flag. Other
+    /// cells are left untouched. Only acts while the game is being played.
+    pub fn toggle_flag(&mut self, x: usize, y: usize) -> MoveOutcome {
+        if let GameState::Alive = self.state {
+            match self.get_cell(x, y).player {
+                PlayerCell::NotDetermined => return self.set([x, y], PlayerCell::Flagged),
+                PlayerCell::Flagged => return self.set([x, y], PlayerCell::NotDetermined),
+                PlayerCell::Question | PlayerCell::Suspected | PlayerCell::Revealed => {},
+            }
+        }
+        MoveOutcome::Ignored(IgnoredReason::NoEffect)
+    }
+
+    /// Marks the cell as suspected if it is not determined yet or has a
+    /// question mark, or removes the mark. Other cells are left untouched.
+    /// Only acts while the game is being played.
+    pub fn toggle_suspected(&mut self, x: usize, y: usize) -> MoveOutcome {
+        if let GameState::Alive = self.state {
+            match self.get_cell(x, y).player {
+                PlayerCell::NotDetermined | PlayerCell::Question => return self.set([x, y], PlayerCell::Suspected),
+                PlayerCell::Suspected => return self.set([x, y], PlayerCell::NotDetermined),
+                PlayerCell::Flagged | PlayerCell::Revealed => {},
+            }
+        }
+        MoveOutcome::Ignored(IgnoredReason::NoEffect)
+    }
+
+    /// Sets or clears a question mark on a cell without going through the
+    /// flag cycle. Setting applies to not determined and flagged cells,
+    /// clearing to question marked ones. Only acts while the game is being
+    /// played.
+    pub fn set_question(&mut self, x: usize, y: usize, on: bool) -> MoveOutcome {
+        if let GameState::Alive = self.state {
+            match (self.get_cell(x, y).player, on) {
+                (PlayerCell::NotDetermined, true) | (PlayerCell::Flagged, true) => return self.set([x, y], PlayerCell::Question),
+                (PlayerCell::Question, false) => return self.set([x, y], PlayerCell::NotDetermined),
+                _ => {},
+            }
+        }
+        MoveOutcome::Ignored(IgnoredReason::NoEffect)
+    }
+
+    /// Reveals every empty cell with its numbered border, e.g., to start a
+    /// game with only the hard part left. Flagged cells are left untouched.
+    /// This is undone and logged as a single move. Only acts while the game
+    /// is being played.
+    pub fn open_all_zero_regions(&mut self) {
+        if self.state != GameState::Alive || self.is_paused() {
+            return;
+        }
+        let before = self.snapshot();
+        let mut last = None;
+        for y in 0..self.size[1] {
+            for x in 0..self.size[0] {
+                if self.is_unknown(x, y) && self.get_cell(x, y).content == CellContent::Nothing(0) {
+                    // Unlike moves, this is not limited by
+                    // `max_cascade_per_call`.
+                    for i in self.flood(x, y, &mut self.cascade_walls()) {
+                        self.cells[i].set_player(PlayerCell::Revealed);
+                    }
+                    last = Some([x, y]);
+                }
+            }
+        }
+        if let Some(ind) = last {
+            self.update_state(ind);
+        }
+        let outcome = self.record(before);
+        self.log([0, 0], MoveAction::OpenZeroRegions, outcome);
+    }
+
+    /// Flags every cell that a single revealed number proves to be a mine,
+    /// as explained by `hint_explained`, as a single move that can be undone.
+    /// Returns how many cells were flagged. Only acts while the game is being
+    /// played.
+    pub fn flag_all_obvious_mines(&mut self) -> usize {
+        if self.state != GameState::Alive || self.is_paused() {
+            return 0;
+        }
+        let before = self.snapshot();
+        let mut res = 0;
+        for deduction in self.hint_explained().into_iter().filter(|deduction| deduction.mine) {
+            if self.restrict_flags && self.flagged >= self.bombs {
+                break;
+            }
+            let (x, y) = deduction.target;
+            self.get_mut_cell(x, y).set_player(PlayerCell::Flagged);
+            self.flagged += 1;
+            res += 1;
+            self.update_state([x, y]);
+        }
+        let outcome = self.record(before);
+        self.log([0, 0], MoveAction::FlagObviousMines, outcome);
+        res
+    }
+
+    /// Counts the revealed cells by number, the empty ones being counted at
+    /// index 0.
+    pub fn number_histogram(&self) -> [usize; 9] {
+        let mut res = [0; 9];
+        for cell in self.cells.iter().filter(|cell| cell.player == PlayerCell::Revealed) {
+            if let CellContent::Nothing(n) = cell.content {
+                res[n as usize] += 1;
+            }
+        }
+        res
+    }
+
+    /// Counts the cells marked with a question mark.
+    pub fn question_count(&self) -> usize {
+        self.cells.iter().filter(|cell| cell.player == PlayerCell::Question).count()
+    }
+
+    /// Removes all the question marks, as a single move that can be undone.
+    /// Only acts while the game is being played.
+    pub fn clear_all_questions(&mut self) {
+        if self.state != GameState::Alive || self.is_paused() {
+            return;
+        }
+        let before = self.snapshot();
+        for i in 0..self.cells.len() {
+            if self.cells[i].player == PlayerCell::Question {
+                self.cells[i].set_player(PlayerCell::NotDetermined);
+            }
+        }
+        let outcome = self.record(before);
+        self.log([0, 0], MoveAction::ClearQuestions, outcome);
+    }
+
+    /// Restarts the game keeping the same bomb layout, e.g., to retry a lost
+    /// board. The game directly starts in the `Alive` state: since the bombs
+    /// are already placed, the first reveal is not guaranteed to be safe.
+    /// Does nothing if the bombs are not placed yet. The event log is kept,
+    /// with the restart logged as a move.
+    pub fn restart_same_layout(&mut self) {
+        if let GameState::Initial = self.state {
+            return;
+        }
+        let before = self.snapshot();
+        for cell in self.cells.iter_mut() {
+            cell.player = PlayerCell::NotDetermined;
+            cell.revealed_at = None;
+        }
+        self.flagged = 0;
+        self.guesses = 0;
+        self.state = GameState::Alive;
+        self.started_at = Some(Instant::now());
+        self.ended_at = None;
+        self.paused_at = None;
+        self.paused_total = Duration::from_secs(0);
+        self.history.clear();
+        self.redo_stack.clear();
+        let outcome = self.outcome_since(&before);
+        self.log([0, 0], MoveAction::Restart, outcome);
+    }
+
+    /// Builds a board in the `Alive` state from a text giving, for each
+    /// cell, both its content and what the player did with it, one line per
+    /// row:
+    ///
+    /// - `.` and `*` are hidden safe cells and bombs,
+    /// - `0` to `8` are revealed cells, the digit being their number,
+    /// - `f` and `F` are flagged safe cells and bombs,
+    /// - `?` and `!` are safe cells and bombs marked with a question mark,
+    /// - `s` and `S` are suspected safe cells and bombs.
+    ///
+    /// Returns an error if the rows have different lengths, if a character
+    /// is unknown or if a revealed number does not match the bombs around.
+    pub fn from_ascii_with_player_state(s: &str) -> Result<Self, RsweeperError> {
+        let rows: Vec<&str> = s.lines().map(str::trim_end).filter(|row| !row.is_empty()).collect();
+        let width = rows.first().map_or(0, |row| row.chars().count());
+        if let Some(row) = rows.iter().find(|row| row.chars().count() != width) {
+            return Err(RsweeperError::Parse(format!("row {:?} does not have {} cells", row, width)));
+        }
+        let size = [width, rows.len()];
+        let mut board = Self::try_new(size, 0)?;
+
+        let mut revealed = Vec::new();
+        for (y, row) in rows.iter().enumerate() {
+            for (x, ch) in row.chars().enumerate() {
+                let (player, bomb) = match ch {
+                    '.' => (PlayerCell::NotDetermined, false),
+                    '*' => (PlayerCell::NotDetermined, true),
+                    'f' => (PlayerCell::Flagged, false),
+                    'F' => (PlayerCell::Flagged, true),
+                    '?' => (PlayerCell::Question, false),
+                    '!' => (PlayerCell::Question, true),
+                    's' => (PlayerCell::Suspected, false),
+                    'S' => (PlayerCell::Suspected, true),
+                    '0'..='8' => {
+                        revealed.push((x, y, ch as u8 - b'0'));
+                        (PlayerCell::Revealed, false)
+                    },
+                    _ => return Err(RsweeperError::Parse(format!("unknown cell {:?} at {:?}", ch, [x, y]))),
+                };
+                if bomb {
+                    board.get_mut_cell(x, y).content = CellContent::Bomb;
+                    board.bombs += 1;
+                }
+                if let PlayerCell::Flagged = player {
+                    board.flagged += 1;
+                }
+                board.get_mut_cell(x, y).set_player(player);
+            }
+        }
+        if board.bombs >= width * rows.len() {
+            return Err(RsweeperError::InvalidBoard(format!("too many bombs to be placed: {}", board.bombs)));
+        }
+        board.recompute_numbers();
+        for (x, y, n) in revealed {
+            if board.get_cell(x, y).content != CellContent::Nothing(n) {
+                return Err(RsweeperError::Parse(format!("cell {:?} does not have {} bombs around", [x, y], n)));
+            }
+        }
+
+        board.total_safe = Some(width * rows.len() - board.bombs);
+        board.state = GameState::Alive;
+        board.started_at = Some(Instant::now());
+        Ok(board)
+    }
+
+    /// Writes the board in the format read by `from_ascii_with_player_state`.
+    /// Revealed bombs, only seen once the game is lost, are written `X`.
+    pub fn to_ascii(&self) -> String {
+        let mut res = String::with_capacity((self.size[0] + 1) * self.size[1]);
+        for y in 0..self.size[1] {
+            for x in 0..self.size[0] {
+                let cell = self.get_cell(x, y);
+                res.push(match (cell.player, cell.content) {
+                    (PlayerCell::NotDetermined, CellContent::Nothing(_)) => '.',
+                    (PlayerCell::NotDetermined, CellContent::Bomb) => '*',
+                    (PlayerCell::Revealed, CellContent::Nothing(n)) => (b'0' + n) as char,
+                    (PlayerCell::Revealed, CellContent::Bomb) => 'X',
+                    (PlayerCell::Flagged, CellContent::Nothing(_)) => 'f',
+                    (PlayerCell::Flagged, CellContent::Bomb) => 'F',
+                    (PlayerCell::Question, CellContent::Nothing(_)) => '?',
+                    (PlayerCell::Question, CellContent::Bomb) => '!',
+                    (PlayerCell::Suspected, CellContent::Nothing(_)) => 's',
+                    (PlayerCell::Suspected, CellContent::Bomb) => 'S',
+                });
+            }
+            res.push('\n');
+        }
+        res
+    }
+
+    /// Encodes the bomb layout, without the progress of the player, as a
+    /// hexadecimal string: each pair of digits is a byte whose bits, lowest
+    /// first, tell whether the next 8 cells, row by row, have a bomb.
+    pub fn to_bomb_code(&self) -> String {
+        self.cells.chunks(8)
+            .map(|chunk| {
+                let byte = chunk.iter().enumerate()
+                    .filter(|(_, cell)| cell.content == CellContent::Bomb)
+                    .fold(0u8, |byte, (i, _)| byte | 1 << i);
+                format!("{:02x}", byte)
+            })
+            .collect()
+    }
+
+    /// Hashes the dimensions and the bomb positions of the board, without
+    /// the progress of the player, e.g., to find duplicate puzzles. Uses
+    /// FNV-1a so that the hash is stable across runs and platforms.
+    pub fn layout_hash(&self) -> u64 {
+        let bombs = self.cells.iter().enumerate()
+            .filter(|(_, cell)| cell.content == CellContent::Bomb)
+            .map(|(i, _)| i as u64);
+        let mut hash = FNV_OFFSET_BASIS;
+        for value in [self.size[0] as u64, self.size[1] as u64].iter().copied().chain(bombs) {
+            for byte in value.to_le_bytes().iter() {
+                hash ^= *byte as u64;
+                hash = hash.wrapping_mul(FNV_PRIME);
+            }
+        }
+        hash
+    }
+
+    /// Builds a board of `cols` x `rows` cells in the `Alive` state from a
+    /// bomb layout encoded by `to_bomb_code`. Returns an error if the code
+    /// does not have the length expected for the board or is not valid
+    /// hexadecimal.
+    pub fn from_bomb_code(code: &str, cols: usize, rows: usize) -> Result<Self, RsweeperError> {
+        let mut board = Self::try_new([cols, rows], 0)?;
+        let cells = cols * rows;
+        if code.len() != 2 * cells.div_ceil(8) {
+            return Err(RsweeperError::Parse(format!("bomb code of {} cells has an invalid length {}", cells, code.len())));
+        }
+        let mut positions = Vec::new();
+        for (i, pair) in code.as_bytes().chunks(2).enumerate() {
+            let byte = Some(pair)
+                .filter(|pair| pair.iter().all(u8::is_ascii_hexdigit))
+                .and_then(|pair| std::str::from_utf8(pair).ok())
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| RsweeperError::Parse(format!("invalid bomb code byte at {}", 2 * i)))?;
+            for bit in 0..8 {
+                if byte & 1 << bit != 0 {
+                    let cell = 8 * i + bit;
+                    if cell >= cells {
+                        return Err(RsweeperError::Parse(format!("bomb code has a bomb out of the board at {}", cell)));
+                    }
+                    positions.push((cell % cols, cell / cols));
+                }
+            }
+        }
+        board.place_bombs_from_positions(&positions)?;
+        Ok(board)
+    }
+
+    /// Returns a new board with the layout rotated by 90 degrees clockwise,
+    /// so that the dimensions are swapped. The player layer is reset.
+    pub fn rotated_90(&self) -> Gameboard {
+        let height = self.size[1];
+        self.transformed([self.size[1], self.size[0]], |x, y| (height - 1 - y, x))
+    }
+
+    /// Returns a new board with the layout mirrored from left to right. The
+    /// player layer is reset.
+    pub fn mirrored_horizontal(&self) -> Gameboard {
+        let width = self.size[0];
+        self.transformed(self.size, |x, y| (width - 1 - x, y))
+    }
+
+    /// Returns a new board of the given size where the content of each cell
+    /// is moved to the position returned by `map`, with the player layer
+    /// reset. Since `map` keeps neighbors adjacent, numbers remain valid.
+    fn transformed<F: Fn(usize, usize) -> (usize, usize)>(&self, size: [usize; 2], map: F) -> Gameboard {
+        let mut board = self.clone();
+        board.size = size;
+        for y in 0..self.size[1] {
+            for x in 0..self.size[0] {
+                let (nx, ny) = map(x, y);
+                let content = self.get_cell(x, y).content;
+                board.get_mut_cell(nx, ny).content = content;
+            }
+        }
+        board.restart_same_layout();
+        board
+    }
+
+    /// Returns the cells whose player layer differs from `other`, with their
+    /// value in `other`, row by row, e.g., to only send the changes to
+    /// another player. Returns an error if the boards do not have the same
+    /// size.
+    pub fn diff(&self, other: &Gameboard) -> Result<Vec<(usize, usize, PlayerCell)>, RsweeperError> {
+        if self.size != other.size {
+            return Err(RsweeperError::InvalidBoard(
+                format!("board {:?} cannot be compared to board {:?}", self.size, other.size)
+            ));
+        }
+        Ok(self.cells.iter().zip(&other.cells).enumerate()
+            .filter(|(_, (cell, other_cell))| cell.player != other_cell.player)
+            .map(|(i, (_, other_cell))| (i % self.size[0], i / self.size[0], other_cell.player))
+            .collect())
+    }
+
+    /// Sets the player layer of the cells listed by `diff`, e.g., received
+    /// from another player's board, without the rules of the moves: nothing
+    /// cascades and the move is not recorded. `flagged` is updated, and the
+    /// game is lost or won as usual if it is `Alive`. Cells out of the board
+    /// are ignored.
+    pub fn apply_diff(&mut self, diff: &[(usize, usize, PlayerCell)]) {
+        let diff: Vec<(usize, usize, PlayerCell)> = diff.iter()
+            .copied()
+            .filter(|&(x, y, _)| self.check_bounds([x, y]).is_ok())
+            .collect();
+        for &(x, y, player) in &diff {
+            self.get_mut_cell(x, y).set_player(player);
+        }
+        self.flagged = self.count_total_flags();
+        self.unlogged_changes = true;
+        // Check the revealed bomb first, if any, as only the given cell is
+        // checked for a loss.
+        let last = diff.iter()
+            .find(|&&(x, y, player)| player == PlayerCell::Revealed && self.get_cell(x, y).content == CellContent::Bomb)
+            .or_else(|| diff.last());
+        if let Some(&(x, y, _)) = last {
+            self.update_state([x, y]);
+        }
+    }
+
+    /// Marks every cell as revealed to display the full solution on end
+    /// screens. Does nothing unless the game is won or lost. The cells the
+    /// player did not reveal get no reveal time, so they are not animated.
+    pub fn reveal_all(&mut self) {
+        if let GameState::Won | GameState::Lost = self.state {
+            let before = self.snapshot();
+            for cell in self.cells.iter_mut() {
+                cell.player = PlayerCell::Revealed;
+            }
+            self.flagged = 0;
+            let outcome = self.outcome_since(&before);
+            self.log([0, 0], MoveAction::RevealAll, outcome);
+        }
+    }
+
+    /// Gets the character with its own font and background color at cell location.
+    /// TODO: pictures.
+    pub fn char_and_colors(&self, ind: [usize; 2]) -> CellDisplay {
+        Self::cell_char_and_colors(self.state, self.get_cell(ind[0], ind[1]))
+    }
+
+    /// Gets the character and colors of a cell when the game is in `state`.
+    fn cell_char_and_colors(state: GameState, cell: &Cell) -> CellDisplay {
+        match state {
+            GameState::Lost => {
+                // If we lost, reveal the bomb positions.
+                // Also show the wrongly flagged cells.
+                match cell.content {
+                    CellContent::Nothing(_) => match cell.player {
+                        PlayerCell::Flagged => (Some(('X', BLACK)), WRONG_FLAG_BACKGROUND),
+                        PlayerCell::Revealed => Self::player_char_and_colors(cell),
+                        _ => (None, ND_BACKGROUND),
+                    },
+                    CellContent::Bomb => (Some(('B', BLACK)), BOMB_BACKGROUND),
+                }
+            },
+            // In other states, show the player input.
+            _ => Self::player_char_and_colors(cell),
+        }
+    }
+
+    /// Gets the character and colors of every cell, as `char_and_colors`,
+    /// row by row, e.g., for renderers other than the piston view.
+    pub fn display_grid(&self) -> Vec<Vec<CellDisplay>> {
+        (0..self.size[1])
+            .map(|y| (0..self.size[0]).map(|x| self.char_and_colors([x, y])).collect())
+            .collect()
+    }
+
+    /// Gets the character with its own font and background color showing the
+    /// player input on a cell.
+    fn player_char_and_colors(cell: &Cell) -> CellDisplay {
+        match cell.player {
+            PlayerCell::NotDetermined => (None, ND_BACKGROUND),
+            PlayerCell::Flagged => (Some(('F', BLACK)), FLAGGED_BACKGROUND),
+            PlayerCell::Question => (Some(('?', BLACK)), ND_BACKGROUND),
+            PlayerCell::Suspected => (Some(('S', BLACK)), SUSPECTED_BACKGROUND),
+            PlayerCell::Revealed => {
+                // Bombs are only revealed when displaying the full solution.
+                match cell.content {
+                    CellContent::Nothing(v) => match v {
+                        0 => (None, REV_BACKGROUND),
+                        1 => (Some(('1', ONE_COLOR)), REV_BACKGROUND),
+                        2 => (Some(('2', TWO_COLOR)), REV_BACKGROUND),
+                        3 => (Some(('3', THREE_COLOR)), REV_BACKGROUND),
+                        4 => (Some(('4', FOUR_COLOR)), REV_BACKGROUND),
+                        5 => (Some(('5', FIVE_COLOR)), REV_BACKGROUND),
+                        6 => (Some(('6', SIX_COLOR)), REV_BACKGROUND),
+                        7 => (Some(('7', SEVEN_COLOR)), REV_BACKGROUND),
+                        8 => (Some(('8', EIGTH_COLOR)), REV_BACKGROUND),
+                        // Not possible to have more than 8
+                        _ => panic!("more than 8 bombs???"),
+                    },
+                    CellContent::Bomb => (Some(('B', BLACK)), BOMB_BACKGROUND),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds an `Alive` board from rows of cells: `*` is a bomb, `F` a
+    /// flagged bomb, `.` a hidden safe cell, `o` a revealed one, `f` a
+    /// flagged one and `?` one marked with a question mark.
+    fn alive_board(rows: &[&str]) -> Gameboard {
+        let mut board = Gameboard::new([rows[0].len(), rows.len()], 0);
+        for (y, row) in rows.iter().enumerate() {
+            for (x, ch) in row.chars().enumerate() {
+                if let '*' | 'F' = ch {
+                    board.set_cell_content(x, y, CellContent::Bomb);
+                }
+            }
+        }
+        board.recompute_numbers();
+        board.state = GameState::Alive;
+        for (y, row) in rows.iter().enumerate() {
+            for (x, ch) in row.chars().enumerate() {
+                let player = match ch {
+                    'o' => PlayerCell::Revealed,
+                    'F' | 'f' => PlayerCell::Flagged,
+                    '?' => PlayerCell::Question,
+                    _ => continue,
+                };
+                board.set_player(x, y, player);
+            }
+        }
+        board
+    }
+
+    #[test]
+    fn progress_is_the_fraction_of_safe_cells_revealed() {
+        assert_eq!(Gameboard::new([5, 5], 3).progress_fraction(), 0.0);
+        let mut board = alive_board(&["*..", "...", "..."]);
+        assert_eq!(board.progress_fraction(), 0.0);
+        board.set([1, 0], PlayerCell::Revealed);
+        assert_eq!(board.progress_fraction(), 0.125);
+        board.set([2, 2], PlayerCell::Revealed);
+        assert_eq!(board.progress_fraction(), 1.0);
+    }
+
+    #[test]
+    fn mercy_moves_the_revealed_bomb_away() {
+        let mut board = alive_board(&["*..", "...", "..."]);
+        board.mercy_moves = 1;
+        board.set([0, 0], PlayerCell::Revealed);
+        assert!(matches!(board.state, GameState::Alive));
+        assert_eq!(board.mercy_moves, 0);
+        assert!(matches!(board.get_cell(0, 0).content, CellContent::Nothing(_)));
+        let bombs = (0..3).flat_map(|y| (0..3).map(move |x| (x, y)))
+            .filter(|&(x, y)| matches!(board.get_cell(x, y).content, CellContent::Bomb))
+            .count();
+        assert_eq!(bombs, 1);
+
+        board.set([1, 1], PlayerCell::Revealed);
+        let bomb = (0..3).flat_map(|y| (0..3).map(move |x| (x, y)))
+            .find(|&(x, y)| matches!(board.get_cell(x, y).content, CellContent::Bomb))
+            .unwrap();
+        board.set([bomb.0, bomb.1], PlayerCell::Revealed);
+        assert!(matches!(board.state, GameState::Lost));
+    }
+
+    #[test]
+    fn frontier_is_the_revealed_numbers_next_to_unknown_cells() {
+        let board = alive_board(&["*..", "oo.", "ooo"]);
+        assert_eq!(board.frontier(), vec![(0, 1), (1, 1), (1, 2), (2, 2)]);
+        assert_eq!(board.unknown_frontier(), vec![(0, 0), (1, 0), (2, 0), (2, 1)]);
+        let board = alive_board(&["*..", "...", "..."]);
+        assert!(board.frontier().is_empty());
+        assert!(board.unknown_frontier().is_empty());
+    }
+
+    #[test]
+    fn csp_combines_several_numbers() {
+        // The 1-2-1 pattern: no number alone tells anything about the cells
+        // above it.
+        let mut board = alive_board(&["*.*", "ooo", "ooo"]);
+        let groups = board.constraint_groups();
+        assert_eq!(groups.len(), 1);
+        assert!(groups[0].constraints.iter().all(|c| c.mines != 0 && c.mines != c.cells.len()));
+        assert!(board.hint_explained().is_empty());
+
+        let mut deductions = board.csp_deductions();
+        deductions.sort_unstable();
+        assert_eq!(deductions, vec![((0, 0), true), ((1, 0), false), ((2, 0), true)]);
+        assert!(board.csp_step());
+        assert!(matches!(board.get_cell(0, 0).player, PlayerCell::Flagged));
+        assert!(matches!(board.get_cell(1, 0).player, PlayerCell::Revealed));
+        assert!(matches!(board.get_cell(2, 0).player, PlayerCell::Flagged));
+        assert!(!board.csp_step());
+    }
+
+    #[test]
+    fn csp_solves_groups_too_large_to_enumerate() {
+        // A row of unknown cells above a row of 1s, all in a single group.
+        let mut rows = vec!["*..".repeat(8)];
+        rows.extend(std::iter::repeat_n("o".repeat(24), 23));
+        let rows: Vec<&str> = rows.iter().map(String::as_str).collect();
+        let mut board = alive_board(&rows);
+        let groups = board.constraint_groups();
+        assert_eq!(groups.len(), 1);
+        assert!(groups[0].cells.len() > CSP_MAX_GROUP_SIZE);
+
+        let deductions = board.csp_deductions();
+        assert!(!deductions.is_empty());
+        for ((x, y), is_mine) in deductions {
+            assert_eq!(matches!(board.get_cell(x, y).content, CellContent::Bomb), is_mine);
+        }
+        assert!(board.csp_step());
+    }
+
+    #[test]
+    fn detects_symmetric_layouts() {
+        let board = alive_board(&["*.*", "...", "*.*"]);
+        assert!(board.is_symmetric(Symmetry::Horizontal));
+        assert!(board.is_symmetric(Symmetry::Vertical));
+        assert!(board.is_symmetric(Symmetry::Rotational));
+        let board = alive_board(&["*..", "...", "..*"]);
+        assert!(!board.is_symmetric(Symmetry::Horizontal));
+        assert!(!board.is_symmetric(Symmetry::Vertical));
+        assert!(board.is_symmetric(Symmetry::Rotational));
+        let board = alive_board(&["**.", "...", "**."]);
+        assert!(board.is_symmetric(Symmetry::Horizontal));
+        assert!(!board.is_symmetric(Symmetry::Vertical));
+        assert!(!Gameboard::new([3, 3], 0).is_symmetric(Symmetry::Rotational));
+    }
+
+    #[test]
+    fn fallible_api_reports_errors() {
+        let mut board = Gameboard::new([3, 3], 1);
+        assert!(matches!(board.try_set([3, 0], PlayerCell::Revealed), Err(RsweeperError::OutOfBounds([3, 0]))));
+        assert!(matches!(board.try_get_cell(0, 3), Err(RsweeperError::OutOfBounds([0, 3]))));
+        assert!(board.try_get_cell(2, 2).is_ok());
+        assert!(board.try_set([1, 1], PlayerCell::Revealed).is_ok());
+        assert!(matches!(Gameboard::try_new([3, 3], 9), Err(RsweeperError::InvalidBoard(_))));
+        assert!(matches!(Gameboard::try_new([0, 3], 0), Err(RsweeperError::InvalidBoard(_))));
+        assert!(Gameboard::try_new([3, 3], 8).is_ok());
+    }
+
+    #[test]
+    fn toggle_flag_and_restricted_flags() {
+        let mut board = alive_board(&["*..", "...", "..."]);
+        board.toggle_flag(1, 1);
+        assert!(matches!(board.get_cell(1, 1).player, PlayerCell::Flagged));
+        assert_eq!(board.flagged, 1);
+        // No more flags than bombs.
+        board.toggle_flag(0, 0);
+        assert!(matches!(board.get_cell(0, 0).player, PlayerCell::NotDetermined));
+        board.toggle_flag(1, 1);
+        assert!(matches!(board.get_cell(1, 1).player, PlayerCell::NotDetermined));
+        assert_eq!(board.flagged, 0);
+
+        board.restrict_flags = false;
+        board.toggle_flag(1, 1);
+        board.toggle_flag(0, 0);
+        assert_eq!(board.flagged, 2);
+    }
+
+    /// Counts the revealed cells of the board.
+    fn revealed(board: &Gameboard) -> usize {
+        (0..board.size[1])
+            .flat_map(|y| (0..board.size[0]).map(move |x| (x, y)))
+            .filter(|&(x, y)| matches!(board.get_cell(x, y).player, PlayerCell::Revealed))
+            .count()
+    }
+
+    /// Gets the content of every cell, row by row.
+    fn layout(board: &Gameboard) -> Vec<CellContent> {
+        board.cells.iter().map(|cell| cell.content).collect()
+    }
+
+    #[test]
+    fn first_reveal_meets_the_minimum_opening() {
+        for _ in 0..20 {
+            let mut board = Gameboard::new([10, 10], 10);
+            board.min_opening = 10;
+            board.set([5, 5], PlayerCell::Revealed);
+            assert!(revealed(&board) >= 10);
+        }
+        // Out of reach, the retry cap is reached and the game starts anyway.
+        let mut board = Gameboard::new([3, 3], 8);
+        board.min_opening = 5;
+        board.set([1, 1], PlayerCell::Revealed);
+        assert_eq!(board.state, GameState::Alive);
+        assert_eq!(revealed(&board), 1);
+        let board = alive_board(&["*...", "....", "....", "...."]);
+        assert_eq!(board.preview_reveal(3, 3), 15);
+        assert_eq!(board.preview_reveal(1, 1), 1);
+        assert_eq!(revealed(&board), 0);
+    }
+
+    #[test]
+    fn question_marks_are_drawn_on_the_regular_background() {
+        let mut board = alive_board(&["*..", "...", "..."]);
+        board.set([1, 1], PlayerCell::Question);
+        assert_eq!(board.char_and_colors([1, 1]), (Some(('?', BLACK)), ND_BACKGROUND));
+        assert_eq!(board.char_and_colors([2, 2]), (None, ND_BACKGROUND));
+    }
+
+    #[test]
+    fn apply_moves_stops_at_the_end_of_the_game() {
+        let mut board = alive_board(&["*..", "...", "..."]);
+        let moves = [(0, 0, PlayerCell::Flagged), (2, 2, PlayerCell::Revealed), (1, 1, PlayerCell::Question)];
+        assert_eq!(board.apply_moves(&moves), 2);
+        assert!(matches!(board.state, GameState::Won));
+
+        let mut board = alive_board(&["*..", "...", "..."]);
+        let moves = [(0, 0, PlayerCell::Flagged), (3, 0, PlayerCell::Revealed), (2, 2, PlayerCell::Revealed)];
+        assert_eq!(board.apply_moves(&moves), 1);
+        assert!(matches!(board.state, GameState::Alive));
+    }
+
+    #[test]
+    fn rejects_boards_larger_than_the_maximum() {
+        assert!(matches!(Gameboard::try_new_with_max_cells([100, 100], 10, 9_999), Err(RsweeperError::InvalidBoard(_))));
+        assert!(Gameboard::try_new_with_max_cells([100, 100], 10, 10_000).is_ok());
+        assert!(matches!(Gameboard::try_new([usize::MAX, 2], 1), Err(RsweeperError::InvalidBoard(_))));
+        assert!(matches!(Gameboard::try_new([1001, 1000], 1), Err(RsweeperError::InvalidBoard(_))));
+    }
+
+    #[test]
+    fn non_square_boards_are_indexed_row_by_row() {
+        let mut board = Gameboard::new([7, 3], 4);
+        board.set([6, 2], PlayerCell::Revealed);
+        assert!(matches!(board.get_cell(6, 2).player, PlayerCell::Revealed));
+        let bombs = board.cells.iter().filter(|cell| matches!(cell.content, CellContent::Bomb)).count();
+        assert_eq!(bombs, 4);
+
+        let board = alive_board(&["*......", ".......", "......*"]);
+        assert!(matches!(board.cells[6 + 2 * 7].content, CellContent::Bomb));
+        assert!(matches!(board.get_cell(5, 1).content, CellContent::Nothing(1)));
+        assert!(matches!(board.get_cell(3, 1).content, CellContent::Nothing(0)));
+    }
+
+    #[test]
+    fn neighbors_stop_at_the_edges() {
+        let board = Gameboard::new([5, 4], 1);
+        assert_eq!(board.neighbors(2, 2).count(), 8);
+        assert_eq!(board.neighbors(0, 0).collect::<Vec<_>>(), vec![(1, 0), (0, 1), (1, 1)]);
+        assert_eq!(board.neighbors(4, 3).count(), 3);
+        assert_eq!(board.neighbors(4, 1).count(), 5);
+        let board = alive_board(&["*..", "...", "..*"]);
+        let bombs: Vec<(usize, usize)> = board.neighbor_cells(1, 1)
+            .filter(|(_, _, cell)| matches!(cell.content, CellContent::Bomb))
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        assert_eq!(bombs, vec![(0, 0), (2, 2)]);
+    }
+
+    #[test]
+    fn detects_unavoidable_fifty_fifty() {
+        let board = alive_board(&["*.", "oo"]);
+        assert!(board.has_unavoidable_5050([0, 1]));
+        let board = alive_board(&["*.*", "ooo", "ooo"]);
+        assert!(!board.has_unavoidable_5050([0, 2]));
+        assert!(matches!(board.get_cell(0, 0).player, PlayerCell::NotDetermined));
+    }
+
+    #[test]
+    fn revealing_records_when() {
+        let mut board = alive_board(&["*..", "...", "..."]);
+        assert!(board.get_cell(1, 1).revealed_at().is_none());
+        board.set([1, 1], PlayerCell::Revealed);
+        assert!(board.get_cell(1, 1).revealed_at().is_some());
+        assert!(board.get_cell(2, 2).revealed_at().is_none());
+    }
+
+    #[test]
+    fn counts_the_flags_on_the_board() {
+        let mut board = alive_board(&["*..", "...", "..."]);
+        board.restrict_flags = false;
+        board.set([0, 0], PlayerCell::Flagged);
+        board.set([2, 2], PlayerCell::Flagged);
+        assert_eq!(board.count_total_flags(), 2);
+        board.set([2, 2], PlayerCell::NotDetermined);
+        assert_eq!(board.count_total_flags(), 1);
+    }
+
+    #[test]
+    fn guesses_are_only_counted_when_tracked() {
+        let layout = ["oo..", "oo*.", "ooo."];
+        let mut board = alive_board(&layout);
+        board.set([3, 0], PlayerCell::Revealed);
+        assert_eq!(board.guess_count(), 0);
+
+        let mut board = alive_board(&layout);
+        board.track_guesses = true;
+        // Nothing tells whether (3, 0) has a bomb.
+        board.set([3, 0], PlayerCell::Revealed);
+        assert_eq!(board.guess_count(), 1);
+        // The numbers of the second column prove that (2, 0) is safe.
+        board.set([2, 0], PlayerCell::Revealed);
+        assert_eq!(board.guess_count(), 1);
+    }
+
+    #[test]
+    fn bounds_the_bombs_left_in_a_region() {
+        let board = alive_board(&["*.*", "ooo", "ooo"]);
+        assert_eq!(board.min_remaining_bombs_in(&[(0, 0), (1, 0), (2, 0)]), 2);
+        assert_eq!(board.min_remaining_bombs_in(&[(0, 0), (1, 0)]), 1);
+        assert_eq!(board.min_remaining_bombs_in(&[(1, 0)]), 0);
+        assert_eq!(board.min_remaining_bombs_in(&[]), 0);
+    }
+
+    #[test]
+    fn classifies_the_difficulty_in_either_orientation() {
+        assert_eq!(Gameboard::new([9, 9], 10).classify_difficulty(), Difficulty::Beginner);
+        assert_eq!(Gameboard::new([16, 16], 40).classify_difficulty(), Difficulty::Intermediate);
+        assert_eq!(Gameboard::new([16, 30], 99).classify_difficulty(), Difficulty::Expert);
+        assert_eq!(Gameboard::new([9, 9], 11).classify_difficulty(), Difficulty::Custom);
+        assert_eq!(Difficulty::Custom.size_and_bombs(), None);
+    }
+
+    #[test]
+    fn summarizes_the_neighbors() {
+        let mut board = alive_board(&["*..", "oo.", "..."]);
+        board.set([0, 0], PlayerCell::Flagged);
+        board.set([2, 2], PlayerCell::Question);
+        let summary = NeighborSummary { flagged: 1, revealed: 1, unrevealed: 6 };
+        assert_eq!(board.neighbor_summary(1, 1), summary);
+        let summary = NeighborSummary { flagged: 0, revealed: 1, unrevealed: 2 };
+        assert_eq!(board.neighbor_summary(2, 0), summary);
+    }
+
+    #[test]
+    fn scenario_helpers_keep_bombs_and_numbers_consistent() {
+        let mut board = Gameboard::new([3, 3], 0);
+        board.set_cell_content(0, 0, CellContent::Bomb);
+        board.set_cell_content(2, 2, CellContent::Bomb);
+        board.set_cell_content(2, 2, CellContent::Nothing(0));
+        board.recompute_numbers();
+        assert_eq!(board.bombs, 1);
+        assert!(matches!(board.get_cell(1, 1).content, CellContent::Nothing(1)));
+        assert!(matches!(board.get_cell(2, 2).content, CellContent::Nothing(0)));
+    }
+
+    #[test]
+    fn wrong_flags_are_shown_on_loss() {
+        let mut board = alive_board(&["*..", "...", "..."]);
+        board.set([1, 1], PlayerCell::Flagged);
+        assert!(board.lost_at().is_none());
+        board.set([0, 0], PlayerCell::Revealed);
+        assert!(matches!(board.state, GameState::Lost));
+        assert!(board.lost_at().is_some());
+        assert_eq!(board.char_and_colors([1, 1]), (Some(('X', BLACK)), WRONG_FLAG_BACKGROUND));
+        assert_eq!(board.char_and_colors([2, 2]), (None, ND_BACKGROUND));
+    }
+
+    #[test]
+    fn undo_and_redo_moves() {
+        let mut board = alive_board(&["*....", ".....", "....."]);
+        board.set([4, 2], PlayerCell::Revealed);
+        let after = board.cells.clone();
+        assert!(board.undo());
+        assert!(board.cells.iter().all(|cell| cell.player == PlayerCell::NotDetermined));
+        assert!(board.redo());
+        assert_eq!(board.cells, after);
+        assert!(!board.redo());
+        assert!(board.undo());
+        board.set([0, 2], PlayerCell::Flagged);
+        assert!(!board.redo());
+    }
+
+    #[test]
+    fn peek_does_not_change_the_board() {
+        let board = alive_board(&["*..", "...", "..."]);
+        assert_eq!(board.peek(0, 0), (PlayerCell::NotDetermined, CellContent::Bomb));
+        assert_eq!(board.peek(1, 1), (PlayerCell::NotDetermined, CellContent::Nothing(1)));
+        assert_eq!(revealed(&board), 0);
+    }
+
+    #[test]
+    fn reveal_all_shows_the_solution() {
+        let mut board = alive_board(&["*..", "...", "..."]);
+        board.reveal_all();
+        assert_eq!(revealed(&board), 0);
+        board.set([0, 0], PlayerCell::Revealed);
+        board.reveal_all();
+        assert!(board.cells.iter().all(|cell| cell.player == PlayerCell::Revealed));
+        assert_eq!(board.char_and_colors([0, 0]).0, Some(('B', BLACK)));
+        assert_eq!(board.char_and_colors([1, 1]).0, Some(('1', ONE_COLOR)));
+        assert!(board.get_cell(0, 0).revealed_at().is_some());
+        assert!(board.get_cell(1, 1).revealed_at().is_none());
+    }
+
+    #[test]
+    fn chord_reveals_the_previewed_cells() {
+        let mut board = alive_board(&["F....", "o....", "....."]);
+        let preview = board.preview_chord(0, 1).unwrap();
+        assert_eq!(preview, vec![(1, 0), (1, 1), (0, 2), (1, 2)]);
+        board.chord(0, 1);
+        for (x, y) in preview {
+            assert_eq!(board.peek(x, y).0, PlayerCell::Revealed);
+        }
+        assert!(board.undo());
+        assert_eq!(board.peek(1, 1).0, PlayerCell::NotDetermined);
+        assert_eq!(board.preview_chord(1, 1), None);
+    }
+
+    #[test]
+    fn elapsed_time_stops_at_the_end_of_the_game() {
+        let mut board = Gameboard::new([3, 3], 1);
+        assert_eq!(board.elapsed(), Duration::from_secs(0));
+        board.set([2, 2], PlayerCell::Revealed);
+        assert!(board.started_at.is_some());
+        assert!(board.lost_at().is_none());
+        let bomb = (0..9).map(|i| [i % 3, i / 3]).find(|ind| board.peek(ind[0], ind[1]).1 == CellContent::Bomb).unwrap();
+        board.set(bomb, PlayerCell::Revealed);
+        assert!(board.lost_at().is_some());
+        assert_eq!(board.elapsed(), board.elapsed());
+    }
+
+    #[test]
+    fn set_question_only_marks_unknown_cells() {
+        let mut board = alive_board(&["*o.", "..f", "..?"]);
+        board.set_question(2, 0, true);
+        board.set_question(1, 0, true);
+        board.set_question(2, 1, true);
+        board.set_question(2, 2, false);
+        assert_eq!(board.peek(2, 0).0, PlayerCell::Question);
+        assert_eq!(board.peek(1, 0).0, PlayerCell::Revealed);
+        assert_eq!(board.peek(2, 1).0, PlayerCell::Question);
+        assert_eq!(board.peek(2, 2).0, PlayerCell::NotDetermined);
+        board.set_question(0, 1, false);
+        assert_eq!(board.peek(0, 1).0, PlayerCell::NotDetermined);
+    }
+
+    #[test]
+    fn restart_keeps_the_layout() {
+        let mut board = Gameboard::new_seeded([9, 9], 10, 5);
+        board.set([4, 4], PlayerCell::Revealed);
+        let before = layout(&board);
+        board.restart_same_layout();
+        assert_eq!(layout(&board), before);
+        assert_eq!(board.state, GameState::Alive);
+        assert_eq!(revealed(&board), 0);
+        assert!(!board.undo());
+    }
+
+    #[test]
+    fn largest_opening_counts_the_borders() {
+        let board = alive_board(&["..*..", "..*..", "..*..", "..*..", "..*.."]);
+        assert_eq!(board.largest_opening(), 10);
+        let board = alive_board(&["*.*", ".*.", "*.*"]);
+        assert_eq!(board.largest_opening(), 0);
+        let board = alive_board(&["....", "....", "*...", "...."]);
+        assert_eq!(board.largest_opening(), 14);
+    }
+
+    #[test]
+    fn rotation_and_mirroring_move_the_bombs() {
+        let board = alive_board(&["*...", "....", "...*"]);
+        let rotated = board.rotated_90();
+        assert_eq!(rotated.size, [3, 4]);
+        assert_eq!(rotated.peek(2, 0).1, CellContent::Bomb);
+        assert_eq!(rotated.peek(0, 3).1, CellContent::Bomb);
+        assert_eq!(rotated.peek(1, 1).1, CellContent::Nothing(1));
+        let mirrored = board.mirrored_horizontal();
+        assert_eq!(mirrored.peek(3, 0).1, CellContent::Bomb);
+        assert_eq!(mirrored.peek(0, 2).1, CellContent::Bomb);
+        assert_eq!(mirrored.state, GameState::Alive);
+        assert_eq!(layout(&rotated.rotated_90().rotated_90().rotated_90()), layout(&board));
+    }
+
+    #[test]
+    fn seeded_presets_are_reproducible() {
+        let mut a = Gameboard::from_difficulty_seeded(Difficulty::Intermediate, 9).unwrap();
+        let mut b = Gameboard::from_difficulty_seeded(Difficulty::Intermediate, 9).unwrap();
+        a.set([8, 8], PlayerCell::Revealed);
+        b.set([8, 8], PlayerCell::Revealed);
+        assert_eq!(layout(&a), layout(&b));
+        assert_eq!(a.size, [16, 16]);
+        assert!(Gameboard::from_difficulty_seeded(Difficulty::Custom, 9).is_none());
+    }
+
+    #[test]
+    fn daily_boards_depend_on_the_date() {
+        let day = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let next_day = NaiveDate::from_ymd_opt(2024, 3, 2).unwrap();
+        assert_eq!(Gameboard::daily_seed(day), Gameboard::daily_seed(day));
+        assert_ne!(Gameboard::daily_seed(day), Gameboard::daily_seed(next_day));
+        let mut boards: Vec<Gameboard> = [day, day, next_day].iter()
+            .map(|&date| Gameboard::daily(Difficulty::Expert, date).unwrap())
+            .collect();
+        for board in boards.iter_mut() {
+            board.set([0, 0], PlayerCell::Revealed);
+        }
+        assert_eq!(layout(&boards[0]), layout(&boards[1]));
+        assert_ne!(layout(&boards[0]), layout(&boards[2]));
+    }
+
+    #[test]
+    fn recomputes_the_numbers_around_a_changed_cell() {
+        let mut board = alive_board(&["*...", "....", "...."]);
+        board.set_cell_content(0, 0, CellContent::Nothing(0));
+        board.set_cell_content(3, 2, CellContent::Bomb);
+        board.recompute_numbers_around(0, 0);
+        board.recompute_numbers_around(3, 2);
+        let mut expected = board.clone();
+        expected.recompute_numbers();
+        assert_eq!(layout(&board), layout(&expected));
+        assert_eq!(board.peek(2, 1).1, CellContent::Nothing(1));
+    }
+
+    #[test]
+    fn set_seed_only_before_the_first_reveal() {
+        let mut a = Gameboard::new([9, 9], 10);
+        let mut b = Gameboard::new([9, 9], 10);
+        assert!(a.set_seed(11));
+        assert!(b.set_seed(11));
+        a.set([4, 4], PlayerCell::Revealed);
+        b.set([4, 4], PlayerCell::Revealed);
+        assert_eq!(layout(&a), layout(&b));
+        assert!(!a.set_seed(12));
+        assert_eq!(a.seed, Some(11));
+    }
+
+    #[test]
+    fn explains_single_number_deductions() {
+        let board = alive_board(&["*oo", "ooo", "ooo"]);
+        let deductions = board.hint_explained();
+        assert_eq!(deductions.len(), 1);
+        assert_eq!(deductions[0].target, (0, 0));
+        assert!(deductions[0].mine);
+        assert_eq!(deductions[0].number, 1);
+        let board = alive_board(&["F.o", "ooo", "ooo"]);
+        assert_eq!(board.hint_explained(), vec![Deduction { source: (2, 0), number: 0, target: (1, 0), mine: false }]);
+    }
+
+    #[test]
+    fn ascii_with_player_state_round_trips() {
+        let mut board = Gameboard::new_seeded([8, 6], 8, 2);
+        board.set([0, 0], PlayerCell::Revealed);
+        let ascii = board.to_ascii();
+        let parsed = Gameboard::from_ascii_with_player_state(&ascii).unwrap();
+        assert_eq!(parsed.to_ascii(), ascii);
+        assert_eq!(parsed.bombs, 8);
+        assert_eq!(parsed.state, GameState::Alive);
+        assert_eq!(Gameboard::from_ascii_with_player_state("F1\n1?\n").unwrap().flagged, 1);
+    }
+
+    #[test]
+    fn rejects_invalid_ascii_boards() {
+        for s in ["..\n...\n", ".x\n..\n", "2*\n..\n", "**\n**\n"] {
+            assert!(Gameboard::from_ascii_with_player_state(s).is_err(), "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn bomb_placement_is_capped_to_the_free_cells() {
+        let mut board = Gameboard::new([3, 3], 2);
+        board.bombs = 20;
+        board.set([1, 1], PlayerCell::Revealed);
+        assert_eq!(board.actual_bomb_count(), 8);
+        assert_eq!(board.bombs, 8);
+        assert_eq!(board.peek(1, 1), (PlayerCell::Revealed, CellContent::Nothing(8)));
+    }
+
+    #[test]
+    fn pausing_stops_the_timer_and_the_moves() {
+        let mut board = Gameboard::new_seeded([9, 9], 10, 3);
+        assert!(!board.pause());
+        board.set([4, 4], PlayerCell::Revealed);
+        assert!(board.pause());
+        assert!(!board.pause());
+        // The timer is frozen while paused.
+        let paused = board.elapsed();
+        assert_eq!(board.elapsed(), paused);
+        let hidden = (0..81).map(|i| [i % 9, i / 9])
+            .find(|ind| board.peek(ind[0], ind[1]).0 == PlayerCell::NotDetermined)
+            .unwrap();
+        assert_eq!(board.set(hidden, PlayerCell::Flagged), MoveOutcome::Ignored(IgnoredReason::Paused));
+        assert!(board.resume());
+        assert!(!board.resume());
+        assert!(board.elapsed() >= paused);
+        assert_eq!(board.set(hidden, PlayerCell::Flagged), MoveOutcome::Applied { pending: Vec::new(), changed: vec![(hidden[0], hidden[1])] });
+        assert_eq!(board.set(hidden, PlayerCell::Flagged), MoveOutcome::Ignored(IgnoredReason::NoEffect));
+    }
+
+    #[test]
+    fn undoing_a_paused_game_keeps_the_timer_consistent() {
+        let mut board = Gameboard::new_seeded([9, 9], 10, 3);
+        board.set([4, 4], PlayerCell::Revealed);
+        assert!(board.pause());
+        // As if the game was paused for a while.
+        board.paused_total = Duration::from_secs(60);
+        assert!(board.resume());
+        // Back to the `Initial` state, before the timer started.
+        while board.undo() {}
+        assert_eq!(board.state, GameState::Initial);
+        assert!(!board.is_paused());
+        assert_eq!(board.elapsed(), Duration::from_secs(0));
+    }
+
+    #[test]
+    fn numbers_are_satisfied_by_as_many_flags() {
+        let board = alive_board(&["Fo.", "oo.", "..."]);
+        assert!(board.is_number_satisfied(1, 0));
+        assert!(board.is_number_satisfied(0, 1));
+        assert!(!board.is_number_satisfied(2, 0));
+        assert!(!board.is_number_satisfied(0, 0));
+        let board = alive_board(&["*o.", "oo.", "..."]);
+        assert!(!board.is_number_satisfied(1, 0));
+    }
+
+    #[test]
+    fn auto_flag_wins_once_the_safe_cells_are_revealed() {
+        let layout = ["**.", "**o", "ooo"];
+        let mut board = alive_board(&layout);
+        board.set([2, 0], PlayerCell::Revealed);
+        assert_eq!(board.state, GameState::Alive);
+
+        let mut board = alive_board(&layout);
+        board.auto_flag_on_clear = true;
+        board.set([2, 0], PlayerCell::Revealed);
+        assert_eq!(board.state, GameState::Won);
+        assert_eq!(board.count_total_flags(), 4);
+    }
+
+    #[test]
+    fn display_grid_is_row_by_row() {
+        let board = alive_board(&["Fo", "o.", "?."]);
+        let grid = board.display_grid();
+        assert_eq!((grid.len(), grid[0].len()), (3, 2));
+        assert_eq!(grid[0][0].0, Some(('F', BLACK)));
+        assert_eq!(grid[0][1], board.char_and_colors([1, 0]));
+        assert_eq!(grid[2][0].0, Some(('?', BLACK)));
+        assert_eq!(grid[1][1], (None, ND_BACKGROUND));
+    }
+
+    #[test]
+    fn cascade_stops_at_flags() {
+        let mut board = alive_board(&["....", ".f..", "....", "...*"]);
+        board.set([0, 0], PlayerCell::Revealed);
+        assert_eq!(board.peek(1, 1).0, PlayerCell::Flagged);
+        assert_eq!(board.peek(3, 3).0, PlayerCell::NotDetermined);
+        assert_eq!(revealed(&board), 14);
+        assert_eq!(board.preview_reveal(0, 0), 1);
+    }
+
+    #[test]
+    fn flagged_counter_matches_the_board_at_the_end() {
+        let mut board = alive_board(&["F*.", "...", "..."]);
+        board.flagged = 1;
+        board.set([1, 0], PlayerCell::Revealed);
+        assert_eq!(board.state, GameState::Lost);
+        assert_eq!(board.flagged, board.count_total_flags());
+        board.reveal_all();
+        assert_eq!(board.flagged, board.count_total_flags());
+    }
+
+    #[test]
+    fn counts_the_cells_left_to_win() {
+        let mut board = alive_board(&["*..", "...", "..*"]);
+        assert_eq!(board.cells_left_to_win(), 7);
+        board.set([1, 0], PlayerCell::Revealed);
+        assert_eq!(board.cells_left_to_win(), 6);
+        assert_eq!(Gameboard::new([3, 3], 2).cells_left_to_win(), 7);
+    }
+
+    #[test]
+    fn places_bombs_at_the_given_positions() {
+        let mut board = Gameboard::new([3, 3], 5);
+        board.place_bombs_from_positions(&[(0, 0), (2, 2), (0, 0)]).unwrap();
+        assert_eq!(board.bombs, 2);
+        assert_eq!(board.state, GameState::Alive);
+        assert!(matches!(board.get_cell(1, 1).content, CellContent::Nothing(2)));
+        assert!(board.place_bombs_from_positions(&[(1, 1)]).is_err());
+
+        let mut board = Gameboard::new([2, 1], 1);
+        assert!(board.place_bombs_from_positions(&[(2, 0)]).is_err());
+        assert!(board.place_bombs_from_positions(&[(0, 0), (1, 0)]).is_err());
+        assert_eq!(board.actual_bomb_count(), 0);
+    }
+
+    #[test]
+    fn questions_are_only_cleared_while_playing() {
+        let mut board = Gameboard::from_ascii_with_player_state("1?.\n1*?\n").unwrap();
+        assert_eq!(board.question_count(), 2);
+        assert!(board.pause());
+        board.clear_all_questions();
+        assert_eq!(board.question_count(), 2);
+        assert!(board.resume());
+        board.clear_all_questions();
+        assert_eq!(board.question_count(), 0);
+        assert!(board.undo());
+        assert_eq!(board.question_count(), 2);
+
+        board.set([1, 1], PlayerCell::Revealed);
+        assert_eq!(board.state, GameState::Lost);
+        board.clear_all_questions();
+        assert_eq!(board.question_count(), 2);
+    }
+
+    #[test]
+    fn only_reveals_start_the_game() {
+        let mut board = Gameboard::new([3, 3], 1);
+        assert_eq!(board.set([0, 0], PlayerCell::Flagged), MoveOutcome::Ignored(IgnoredReason::NotStarted));
+        assert_eq!(board.set([0, 0], PlayerCell::Question), MoveOutcome::Ignored(IgnoredReason::NotStarted));
+        assert_eq!(board.state, GameState::Initial);
+        board.set([0, 0], PlayerCell::Revealed);
+        assert_ne!(board.state, GameState::Initial);
+    }
+
+    #[test]
+    fn opens_every_zero_region_at_once() {
+        let mut board = alive_board(&["..f.", "....", "*..*", "....", "...."]);
+        board.open_all_zero_regions();
+        assert_eq!(board.peek(2, 0).0, PlayerCell::Flagged);
+        assert_eq!(board.peek(0, 2).0, PlayerCell::NotDetermined);
+        assert_eq!(board.peek(3, 2).0, PlayerCell::NotDetermined);
+        assert_eq!(revealed(&board), 15);
+        assert!(board.undo());
+        assert_eq!(revealed(&board), 0);
+    }
+
+    #[test]
+    fn logs_the_moves_as_csv() {
+        let mut board = alive_board(&["*..", "...", "..."]);
+        board.set([1, 0], PlayerCell::Question);
+        board.set([0, 0], PlayerCell::Flagged);
+        board.set([0, 0], PlayerCell::Flagged);
+        board.clear_all_questions();
+        board.open_all_zero_regions();
+        let actions: Vec<_> = board.events().iter().map(|event| (event.x, event.y, event.action)).collect();
+        assert_eq!(actions, vec![
+            (1, 0, MoveAction::Set(PlayerCell::Question)),
+            (0, 0, MoveAction::Set(PlayerCell::Flagged)),
+            (0, 0, MoveAction::Set(PlayerCell::Flagged)),
+            (0, 0, MoveAction::ClearQuestions),
+            (0, 0, MoveAction::OpenZeroRegions),
+        ]);
+        let csv = board.export_csv();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[0], "time,x,y,action,outcome");
+        assert!(lines[3].ends_with(",0,0,flag,no effect"));
+        assert!(lines[5].ends_with(",0,0,open zero regions,applied"));
+    }
+
+    #[test]
+    fn solver_clone_drops_the_history_and_the_log() {
+        let mut board = alive_board(&["*..", "...", "..."]);
+        board.set([2, 2], PlayerCell::Revealed);
+        let clone = board.solver_clone();
+        assert_eq!(revealed(&clone), revealed(&board));
+        assert_eq!(clone.state, board.state);
+        assert!(clone.events().is_empty());
+        assert!(clone.history.is_empty());
+    }
+
+    #[test]
+    fn large_cascades_do_not_overflow_the_stack() {
+        let mut board = Gameboard::new([400, 400], 0);
+        board.state = GameState::Alive;
+        board.set([0, 0], PlayerCell::Revealed);
+        assert_eq!(revealed(&board), 400 * 400);
+    }
+
+    #[test]
+    fn presets_set_the_default_opening() {
+        let board = Gameboard::from_difficulty(Difficulty::Beginner).unwrap();
+        assert_eq!(board.min_opening, 20);
+        let board = Gameboard::from_difficulty_seeded(Difficulty::Expert, 3).unwrap();
+        assert_eq!(board.min_opening, 0);
+        assert_eq!(board.seed, Some(3));
+        assert!(Gameboard::from_difficulty(Difficulty::Custom).is_none());
+    }
+
+    #[test]
+    fn bomb_codes_round_trip() {
+        let mut board = Gameboard::new_seeded([7, 5], 9, 2);
+        board.set([3, 3], PlayerCell::Revealed);
+        let code = board.to_bomb_code();
+        assert_eq!(code.len(), 10);
+        let decoded = Gameboard::from_bomb_code(&code, 7, 5).unwrap();
+        assert_eq!(layout(&decoded), layout(&board));
+        assert_eq!(decoded.bombs, 9);
+    }
+
+    #[test]
+    fn rejects_invalid_bomb_codes() {
+        assert!(Gameboard::from_bomb_code("00000000", 7, 5).is_err());
+        assert!(Gameboard::from_bomb_code("zz00000000", 7, 5).is_err());
+        assert!(Gameboard::from_bomb_code("+f00000000", 7, 5).is_err());
+        assert!(Gameboard::from_bomb_code("00000000f0", 7, 5).is_err());
+        assert!(Gameboard::from_bomb_code("é00000000", 7, 5).is_err());
+    }
+
+    #[test]
+    fn deduction_status_of_cells() {
+        let board = alive_board(&["*o.", "oo.", "ooo"]);
+        assert_eq!(board.deduction_status(0, 0), Some(Deduced::Mine));
+        assert_eq!(board.deduction_status(2, 1), Some(Deduced::Safe));
+        assert_eq!(board.deduction_status(0, 1), None);
+    }
+
+    #[test]
+    fn rewinds_to_before_the_loss() {
+        let mut board = alive_board(&["*..", "...", "..."]);
+        assert!(!board.rewind_to_before_loss());
+        board.set([0, 0], PlayerCell::Revealed);
+        assert_eq!(board.state, GameState::Lost);
+        assert!(board.rewind_to_before_loss());
+        assert_eq!(board.state, GameState::Alive);
+        assert_eq!(board.peek(0, 0).0, PlayerCell::NotDetermined);
+    }
+
+    #[test]
+    fn cascade_is_limited_per_call() {
+        let mut board = alive_board(&["..........", "..........", "..........", "..........", ".........*"]);
+        board.max_cascade_per_call = Some(5);
+        let mut pending = match board.set([0, 0], PlayerCell::Revealed) {
+            MoveOutcome::Applied { pending, .. } => pending,
+            outcome => panic!("{:?}", outcome),
+        };
+        assert_eq!(revealed(&board), 5);
+        let mut calls = 0;
+        while !pending.is_empty() {
+            let before = revealed(&board);
+            pending = match board.continue_cascade(&pending) {
+                MoveOutcome::Applied { pending, .. } => pending,
+                outcome => panic!("{:?}", outcome),
+            };
+            assert!(revealed(&board) - before <= 5);
+            calls += 1;
+        }
+        assert!(calls > 3);
+        assert_eq!(board.cells_left_to_win(), 0);
+    }
+
+    #[test]
+    fn orthogonal_boards_only_count_four_neighbors() {
+        let mut board = Gameboard::new_orthogonal([5, 5], 1);
+        board.place_bombs_from_positions(&[(1, 1)]).unwrap();
+        assert_eq!(board.neighbors(2, 2).count(), 4);
+        assert_eq!(board.peek(0, 0).1, CellContent::Nothing(0));
+        assert_eq!(board.peek(1, 0).1, CellContent::Nothing(1));
+        board.set([4, 4], PlayerCell::Revealed);
+        // Surrounded by numbers, the corner is not reached by the cascade.
+        assert_eq!(board.peek(0, 0).0, PlayerCell::NotDetermined);
+
+        let mut board = Gameboard::new([5, 5], 1);
+        board.place_bombs_from_positions(&[(1, 1)]).unwrap();
+        assert_eq!(board.neighbors(2, 2).count(), 8);
+        assert_eq!(board.peek(0, 0).1, CellContent::Nothing(1));
+    }
+
+    #[test]
+    fn estimates_the_win_probability() {
+        let board = alive_board(&["*.F", "ooF", "ooF"]);
+        assert!((board.win_probability() - 0.5).abs() < 1e-9);
+        let board = alive_board(&["*o.", "oo.", "ooo"]);
+        assert_eq!(board.win_probability(), 1.0);
+        let mut board = Gameboard::new_seeded([16, 16], 40, 1);
+        board.set([8, 8], PlayerCell::Revealed);
+        let p = board.win_probability();
+        assert!(p > 0.0 && p <= 1.0);
+    }
+
+    #[test]
+    fn enclosed_bombs_are_won_with_auto_flag() {
+        // The bomb in the corner touches no revealed number.
+        let mut board = alive_board(&["**o", "**o", "ooo"]);
+        assert_eq!(board.win_probability(), 0.0);
+        board.auto_flag_on_clear = true;
+        assert_eq!(board.win_probability(), 1.0);
+    }
+
+    #[test]
+    fn caches_the_safe_cells() {
+        assert_eq!(Gameboard::new([4, 4], 3).total_safe_cells(), 13);
+        let mut board = alive_board(&["*..", "...", "..*"]);
+        assert_eq!(board.total_safe_cells(), 7);
+        board.mercy_moves = 1;
+        board.set([0, 0], PlayerCell::Revealed);
+        assert_eq!(board.total_safe_cells(), 7);
+        let mut board = Gameboard::new_seeded([9, 9], 10, 4);
+        board.set([4, 4], PlayerCell::Revealed);
+        assert_eq!(board.total_safe_cells(), 71);
+    }
+
+    #[test]
+    fn reports_the_cells_a_move_changed() {
+        let mut board = alive_board(&["*..", "...", "..."]);
+        match board.set([1, 0], PlayerCell::Revealed) {
+            MoveOutcome::Applied { changed, .. } => assert_eq!(changed, vec![(1, 0)]),
+            outcome => panic!("{:?}", outcome),
+        }
+        match board.set([0, 0], PlayerCell::Revealed) {
+            MoveOutcome::Applied { changed, .. } => assert!(changed.contains(&(0, 0))),
+            outcome => panic!("{:?}", outcome),
+        }
+    }
+
+    #[test]
+    fn bombs_can_only_change_before_the_first_reveal() {
+        let mut board = Gameboard::new_seeded([4, 4], 2, 1);
+        assert!(board.set_bombs(16).is_err());
+        board.set_bombs(5).unwrap();
+        assert_eq!(board.total_safe_cells(), 11);
+        board.set([0, 0], PlayerCell::Revealed);
+        assert_eq!(board.actual_bomb_count(), 5);
+        assert!(board.set_bombs(3).is_err());
+    }
+
+    #[test]
+    fn finds_the_nearest_safe_cell() {
+        let board = alive_board(&[".*.*.", "ooooo", "ooooo"]);
+        assert_eq!(board.nearest_safe_cell((0, 2)), Some((0, 0)));
+        assert_eq!(board.nearest_safe_cell((4, 2)), Some((4, 0)));
+        assert_eq!(alive_board(&["*..", "...", "..."]).nearest_safe_cell((0, 0)), None);
+    }
+
+    #[test]
+    fn layout_hash_ignores_the_player() {
+        let a = alive_board(&["*..", "...", "..*"]);
+        let mut b = alive_board(&["*..", "...", "..*"]);
+        b.set([1, 1], PlayerCell::Revealed);
+        assert_eq!(a.layout_hash(), b.layout_hash());
+        assert_ne!(a.layout_hash(), alive_board(&["*..", "..*", "..."]).layout_hash());
+        assert_ne!(a.layout_hash(), alive_board(&["*..", "...", "..*", "..."]).layout_hash());
+    }
+
+    #[test]
+    fn expanding_keeps_the_numbers_right() {
+        let mut board = alive_board(&["..*", "...", "..."]);
+        board.set([0, 2], PlayerCell::Revealed);
+        board.expand(2, 1, 3);
+        assert_eq!(board.size, [5, 4]);
+        assert_eq!(board.bombs, 4);
+        assert_eq!(board.actual_bomb_count(), 4);
+        let mut recomputed = board.clone();
+        recomputed.recompute_numbers();
+        assert_eq!(layout(&board), layout(&recomputed));
+        assert_eq!(board.state, GameState::Alive);
+        assert_eq!(board.peek(0, 2).0, PlayerCell::Revealed);
+    }
+
+    #[test]
+    fn reveals_rectangular_regions() {
+        let mut board = alive_board(&["*...", "....", "...*", "...."]);
+        board.reveal_region(2, 0, 1, 1);
+        for y in 0..2 {
+            for x in 1..3 {
+                assert_eq!(board.peek(x, y).0, PlayerCell::Revealed);
+            }
+        }
+        assert_eq!(board.state, GameState::Alive);
+        board.reveal_region(0, 0, 10, 10);
+        assert_eq!(board.state, GameState::Lost);
+    }
+
+    #[test]
+    fn flag_balance_counts_the_flags_on_the_board() {
+        let mut board = Gameboard::from_ascii_with_player_state("1*.\n11.\n").unwrap();
+        assert_eq!(board.flag_balance(), -1);
+        board.restrict_flags = false;
+        board.set([1, 0], PlayerCell::Flagged);
+        board.set([2, 0], PlayerCell::Flagged);
+        assert_eq!(board.flag_balance(), 1);
+        // Whatever the counter says.
+        board.flagged = 0;
+        assert_eq!(board.flag_balance(), 1);
+    }
+
+    #[test]
+    fn monte_carlo_winrate_is_reproducible() {
+        let board = alive_board(&["*....", ".....", ".....", "....."]);
+        assert_eq!(board.monte_carlo_winrate((4, 3), 20, 1), 1.0);
+        let board = Gameboard::new([9, 9], 10);
+        let rate = board.monte_carlo_winrate((4, 4), 20, 1);
+        assert!(rate > 0.0 && rate <= 1.0);
+        assert_eq!(rate, board.monte_carlo_winrate((4, 4), 20, 1));
+        assert_eq!(board.monte_carlo_winrate((4, 4), 0, 1), 0.0);
+    }
+
+    #[test]
+    fn continued_cascades_are_undone_with_their_move() {
+        let mut board = alive_board(&["..........", "..........", "..........", "..........", ".........*"]);
+        board.max_cascade_per_call = Some(5);
+        let mut pending = match board.set([0, 0], PlayerCell::Revealed) {
+            MoveOutcome::Applied { pending, .. } => pending,
+            outcome => panic!("{:?}", outcome),
+        };
+        while !pending.is_empty() {
+            pending = match board.continue_cascade(&pending) {
+                MoveOutcome::Applied { pending, .. } => pending,
+                outcome => panic!("{:?}", outcome),
+            };
+        }
+        assert_eq!(board.cells_left_to_win(), 0);
+        // The whole opening is undone at once.
+        assert!(board.undo());
+        assert_eq!(revealed(&board), 0);
+        assert!(!board.undo());
+    }
+
+    #[test]
+    fn cascades_continued_after_another_move_are_moves_of_their_own() {
+        let mut board = alive_board(&["..........", "..........", "..........", "..........", ".........*"]);
+        board.max_cascade_per_call = Some(5);
+        board.set([0, 0], PlayerCell::Revealed);
+        let pending = board.cascade_to_continue().to_vec();
+        assert!(!pending.is_empty());
+        board.set([9, 4], PlayerCell::Flagged);
+        let before = revealed(&board);
+        assert!(matches!(board.continue_cascade(&pending), MoveOutcome::Applied { .. }));
+        let event = board.events().last().unwrap();
+        assert_eq!((event.x, event.y, event.action), (pending[0].0, pending[0].1, MoveAction::ContinueCascade));
+        // Only the continuation is undone, the flag is kept.
+        assert!(board.undo());
+        assert_eq!(revealed(&board), before);
+        assert_eq!(board.peek(9, 4).0, PlayerCell::Flagged);
+        // Once the move is undone, there is nothing to continue from.
+        assert!(board.undo() && board.undo());
+        assert_eq!(board.continue_cascade(&pending), MoveOutcome::Ignored(IgnoredReason::NoEffect));
+    }
+
+    #[test]
+    fn set_player_keeps_the_flag_count() {
+        let mut board = alive_board(&["*..", "...", "..."]);
+        board.set_player(0, 0, PlayerCell::Flagged);
+        assert_eq!(board.flagged, 1);
+        board.set_player(0, 0, PlayerCell::Revealed);
+        assert_eq!(board.flagged, 0);
+        assert_eq!(board.state, GameState::Lost);
+    }
+
+    #[test]
+    fn exports_the_constraints() {
+        let board = alive_board(&["F*.", "ooo", "ooo"]);
+        let constraints = board.export_constraints();
+        assert!(constraints.contains(&(vec![(1, 0), (2, 0)], 1)));
+    }
+
+    /// Returns scripted coordinates.
+    struct Scripted(Vec<usize>);
+
+    impl BoardRng for Scripted {
+        fn gen_coord(&mut self, max: usize) -> usize {
+            let coord = self.0.remove(0);
+            assert!(coord < max);
+            coord
+        }
+    }
+
+    #[test]
+    fn places_bombs_with_any_generator() {
+        let mut board = Gameboard::new([4, 4], 2);
+        // The first click is skipped.
+        board.place_bombs_with_rng([0, 0], &mut Scripted(vec![0, 0, 3, 1, 2, 3])).unwrap();
+        assert_eq!(board.state, GameState::Alive);
+        assert_eq!(board.peek(3, 1).1, CellContent::Bomb);
+        assert_eq!(board.peek(2, 3).1, CellContent::Bomb);
+        assert!(board.place_bombs_with_rng([0, 0], &mut rand::thread_rng()).is_err());
+        board.set([0, 0], PlayerCell::Revealed);
+        assert_eq!(board.peek(3, 1).1, CellContent::Bomb);
+    }
+
+    #[test]
+    fn counts_unrevealed_islands() {
+        assert_eq!(alive_board(&["*.o..", "..o.*", "ooooo", "o.o.o"]).unrevealed_island_count(), 4);
+        assert_eq!(alive_board(&["*.o", "o.o"]).unrevealed_island_count(), 1);
+    }
+
+    #[test]
+    fn flags_the_obvious_mines() {
+        let mut board = alive_board(&["*o.", "oo.", "ooo"]);
+        assert_eq!(board.flag_all_obvious_mines(), 1);
+        assert_eq!(board.peek(0, 0).0, PlayerCell::Flagged);
+        assert_eq!(board.flagged, 1);
+        assert!(board.undo());
+        assert_eq!(board.flagged, 0);
+    }
+
+    #[test]
+    fn histogram_of_revealed_numbers() {
+        let board = alive_board(&["*o.", "ooo", "ooo"]);
+        assert_eq!(board.number_histogram(), [4, 3, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn bombs_detonate_from_the_nearest() {
+        let board = alive_board(&["*...*", ".....", "..*..", "....."]);
+        assert_eq!(board.bomb_detonation_order((4, 0)), vec![(4, 0), (2, 2), (0, 0)]);
+    }
+
+    #[test]
+    fn generates_boards_within_a_3bv_range() {
+        assert_eq!(alive_board(&["*.*", "...", "*.*"]).compute_3bv(), 5);
+        let board = Gameboard::new_with_3bv_range(Difficulty::Beginner, 5, 15, 7, 200).unwrap();
+        assert!((5..=15).contains(&board.compute_3bv()));
+        assert!(Gameboard::new_with_3bv_range(Difficulty::Beginner, 500, 600, 7, 5).is_none());
+    }
+
+    #[test]
+    fn suspected_cells_are_not_flags() {
+        let mut board = alive_board(&["*o", "oo"]);
+        board.toggle_suspected(0, 0);
+        assert_eq!(board.peek(0, 0).0, PlayerCell::Suspected);
+        assert_eq!(board.flagged, 0);
+        assert_eq!(board.state, GameState::Alive);
+        board.toggle_suspected(0, 0);
+        board.toggle_flag(0, 0);
+        assert_eq!(board.state, GameState::Won);
+    }
+
+    #[test]
+    fn reveal_neighbors_ignores_the_flags_count() {
+        let mut board = alive_board(&["*..", ".o.", "..."]);
+        assert!(matches!(board.reveal_neighbors(1, 1), MoveOutcome::Applied { .. }));
+        assert_eq!(board.state, GameState::Lost);
+        let mut board = alive_board(&["F..", ".o.", "..."]);
+        board.chord(1, 1);
+        assert_eq!(board.state, GameState::Won);
+    }
+
+    #[test]
+    fn average_time_between_applied_reveals() {
+        let event = |millis: u64, action: MoveAction, applied: bool| MoveEvent {
+            time: Duration::from_millis(millis),
+            x: 0,
+            y: 0,
+            action,
+            outcome: if applied {
+                MoveOutcome::Applied { pending: Vec::new(), changed: Vec::new() }
+            } else {
+                MoveOutcome::Ignored(IgnoredReason::NoEffect)
+            },
+        };
+        let mut board = alive_board(&["*..", "...", "..."]);
+        board.events = vec![
+            event(0, MoveAction::Set(PlayerCell::Revealed), true),
+            event(500, MoveAction::Set(PlayerCell::Flagged), true),
+            event(1000, MoveAction::Set(PlayerCell::Revealed), true),
+            event(1500, MoveAction::Set(PlayerCell::Revealed), false),
+            event(4000, MoveAction::Set(PlayerCell::Revealed), true),
+        ];
+        assert_eq!(board.average_time_between_reveals(), Some(Duration::from_millis(2000)));
+        board.events.truncate(1);
+        assert_eq!(board.average_time_between_reveals(), None);
+    }
+
+    #[test]
+    fn diffs_the_player_layers() {
+        let a = alive_board(&["*..", "...", "..."]);
+        let mut b = a.clone();
+        b.set([1, 0], PlayerCell::Revealed);
+        b.set([0, 0], PlayerCell::Flagged);
+        assert_eq!(a.diff(&b).unwrap(), vec![(0, 0, PlayerCell::Flagged), (1, 0, PlayerCell::Revealed)]);
+        assert!(a.diff(&a).unwrap().is_empty());
+        assert!(a.diff(&alive_board(&["*.", ".."])).is_err());
+    }
+
+    #[test]
+    fn diffs_apply_back() {
+        let mut a = alive_board(&["*..", "...", "..."]);
+        let mut b = a.clone();
+        a.set([2, 2], PlayerCell::Revealed);
+        a.set([0, 0], PlayerCell::Flagged);
+        let diff = b.diff(&a).unwrap();
+        assert_eq!(diff.len(), 9);
+        b.apply_diff(&diff);
+        assert!(b.diff(&a).unwrap().is_empty());
+        assert_eq!(b.flagged, a.flagged);
+        assert_eq!(b.state, GameState::Won);
+        assert!(a.diff(&alive_board(&["*.", ".."])).is_err());
+
+        let mut c = alive_board(&["*..", "...", "..."]);
+        c.apply_diff(&[(1, 1, PlayerCell::Revealed), (0, 0, PlayerCell::Revealed), (9, 9, PlayerCell::Revealed)]);
+        assert_eq!(c.state, GameState::Lost);
+    }
+
+    #[test]
+    fn counts_the_proven_mines() {
+        assert_eq!(alive_board(&["o*.", "**.", "..."]).proven_mine_count(), 3);
+        assert_eq!(alive_board(&["o*.", "F*.", "..."]).proven_mine_count(), 2);
+    }
+
+    #[test]
+    fn guaranteed_opening_keeps_the_first_click_neighbors_safe() {
+        for seed in 0..100 {
+            let mut board = Gameboard::new_seeded([9, 9], 30, seed);
+            board.guaranteed_opening = true;
+            board.set([0, 4], PlayerCell::Revealed);
+            assert_eq!(board.peek(0, 4).1, CellContent::Nothing(0));
+            assert!(revealed(&board) > 1);
+            assert_eq!(board.actual_bomb_count(), 30);
+        }
+        // Not enough room, the rule is relaxed.
+        let mut board = Gameboard::new([3, 3], 8);
+        board.guaranteed_opening = true;
+        board.set([1, 1], PlayerCell::Revealed);
+        assert_eq!(board.actual_bomb_count(), 8);
+    }
+
+    #[test]
+    fn state_predicates() {
+        let mut board = Gameboard::new([3, 3], 1);
+        for &(state, won, lost) in &[
+            (GameState::Initial, false, false),
+            (GameState::Alive, false, false),
+            (GameState::Won, true, false),
+            (GameState::Lost, false, true),
+        ] {
+            board.state = state;
+            assert_eq!((board.is_won(), board.is_lost(), board.is_over()), (won, lost, won || lost));
+        }
+    }
+
+    #[test]
+    fn force_lose_reveals_a_bomb() {
+        let mut board = Gameboard::new([5, 5], 4);
+        board.force_lose();
+        assert!(board.is_lost());
+        assert!(board.cells.iter().any(|cell| cell.content == CellContent::Bomb && cell.player == PlayerCell::Revealed));
+        let mut board = alive_board(&["..F", "...", "..."]);
+        board.force_lose();
+        assert!(board.is_lost());
+        assert_eq!(board.flagged, 0);
+        assert_eq!(board.peek(2, 0).0, PlayerCell::Revealed);
+    }
+
+    #[test]
+    fn seeded_boards_relocate_and_expand_the_same_way() {
+        let play = || {
+            let mut board = Gameboard::new_seeded([9, 9], 10, 3);
+            board.set([4, 4], PlayerCell::Revealed);
+            let bomb = (0..81).map(|i| [i % 9, i / 9])
+                .find(|&[x, y]| board.get_cell(x, y).content == CellContent::Bomb)
+                .unwrap();
+            board.mercy_moves = 1;
+            board.set(bomb, PlayerCell::Revealed);
+            assert_eq!(board.mercy_moves, 0);
+            board.expand(3, 3, 5);
+            board
+        };
+        assert_eq!(layout(&play()), layout(&play()));
+    }
+}
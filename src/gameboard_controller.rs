@@ -1,15 +1,47 @@
 //! Gameboard controller.
 
-use piston::{Button, GenericEvent, MouseButton};
+use std::time::{Duration, Instant};
+
+use piston::{Button, GenericEvent, Key, MouseButton};
 
 use crate::{Gameboard, gameboard::PlayerCell};
 
+/// What a right click does to a cell.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FlagMode {
+    /// Cycles through flagged, question mark and not determined.
+    Cycle,
+    /// Toggles the flag, without question marks.
+    FlagOnly,
+}
+
+/// What the space key does to a cell.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ActionMode {
+    /// Reveals the cell.
+    Reveal,
+    /// Toggles the flag of the cell.
+    Flag,
+}
+
 /// Handles events for Sudoku game.
 pub struct GameboardController {
     /// Stores the gameboard state.
     pub gameboard: Gameboard,
     /// The last selected cell, if any.
     pub selected_cell: Option<[usize; 2]>,
+    /// The cell currently under the mouse cursor, if any.
+    pub hovered_cell: Option<[usize; 2]>,
+    /// What a right click does.
+    pub flag_mode: FlagMode,
+    /// How long the left button must be held on a cell to toggle its flag
+    /// instead of revealing it, for devices without a right button. If
+    /// `None`, cells are revealed as soon as the button is pressed.
+    pub long_press_threshold: Option<Duration>,
+    /// What the space key does, toggled with the M key.
+    pub action_mode: ActionMode,
+    /// When the left button was pressed and on which cell, while it is held.
+    pressed: Option<(Instant, [usize; 2])>,
     /// The last mouse cursor position.
     cursor_pos: [f64; 2],
 }
@@ -20,12 +52,17 @@ impl GameboardController {
         Self {
             gameboard,
             selected_cell: None,
+            hovered_cell: None,
+            flag_mode: FlagMode::Cycle,
+            long_press_threshold: None,
+            action_mode: ActionMode::Reveal,
+            pressed: None,
             cursor_pos: [0.0; 2],
         }
     }
 
-    /// Set the selected cell, or None if it is out of the grid.
-    fn find_selected_cell(&mut self, pos: [f64; 2], cell_size: [f64; 2]) {
+    /// Gets the cell under the mouse cursor, or None if it is out of the grid.
+    fn cell_at(&self, pos: [f64; 2], cell_size: [f64; 2]) -> Option<[usize; 2]> {
         // Find coordinates relative to upper left corner.
         let x = self.cursor_pos[0] - pos[0];
         let y = self.cursor_pos[1] - pos[1];
@@ -33,47 +70,328 @@ impl GameboardController {
         let size_x = cell_size[0] * (self.gameboard.size[0] as f64);
         let size_y = cell_size[1] * (self.gameboard.size[1] as f64);
         // Check that coordinates are inside board boundaries.
-        self.selected_cell = if x >= 0.0 && x < size_x && y >= 0.0 && y < size_y {
+        if x >= 0.0 && x < size_x && y >= 0.0 && y < size_y {
             // Compute the cell position.
             let cell_x = (x / cell_size[0]) as usize;
             let cell_y = (y / cell_size[1]) as usize;
             Some([cell_x, cell_y])
         } else {
             None
-        };
+        }
+    }
+
+    /// Set the selected cell, or None if it is out of the grid.
+    fn find_selected_cell(&mut self, pos: [f64; 2], cell_size: [f64; 2]) {
+        self.selected_cell = self.cell_at(pos, cell_size);
+    }
+
+    /// Set the selected cell like `find_selected_cell` and return it, unless
+    /// the cell under the cursor is already revealed, so that clicks on it do
+    /// not move the highlight.
+    fn find_selected_unrevealed_cell(&mut self, pos: [f64; 2], cell_size: [f64; 2]) -> Option<[usize; 2]> {
+        let ind = self.cell_at(pos, cell_size);
+        if let Some([x, y]) = ind {
+            if let PlayerCell::Revealed = self.gameboard.get_cell(x, y).get_player_cell() {
+                return None;
+            }
+        }
+        self.selected_cell = ind;
+        ind
     }
 
     /// Handles events.
     pub fn event<E: GenericEvent>(&mut self, pos: [f64; 2], cell_size: [f64; 2], e: &E) {
-        if let Some(pos) = e.mouse_cursor_args() {
-            self.cursor_pos = pos;
+        if let Some(cursor_pos) = e.mouse_cursor_args() {
+            self.cursor_pos = cursor_pos;
+            self.hovered_cell = self.cell_at(pos, cell_size);
+        }
+
+        // The cursor left the window, nothing is hovered anymore.
+        if let Some(false) = e.cursor_args() {
+            self.hovered_cell = None;
         }
 
         if let Some(Button::Mouse(MouseButton::Left)) = e.press_args() {
-            self.find_selected_cell(pos, cell_size);
-            match self.selected_cell {
-                Some(ind) => {
+            if let Some(ind) = self.find_selected_unrevealed_cell(pos, cell_size) {
+                match self.long_press_threshold {
+                    // Wait for the release to know how long the press was.
+                    Some(_) => self.pressed = Some((Instant::now(), ind)),
+                    None => {
+                        self.gameboard.set(ind, PlayerCell::Revealed);
+                    },
+                }
+            }
+        }
+
+        if let Some(Button::Mouse(MouseButton::Left)) = e.release_args() {
+            if let (Some((pressed_at, ind)), Some(threshold)) = (self.pressed.take(), self.long_press_threshold) {
+                if pressed_at.elapsed() >= threshold {
+                    self.gameboard.toggle_flag(ind[0], ind[1]);
+                } else {
                     self.gameboard.set(ind, PlayerCell::Revealed);
-                },
-                None => {},
+                }
             }
         }
 
-        if let Some(Button::Mouse(MouseButton::Right)) = e.press_args() {
+        if let Some(Button::Keyboard(Key::M)) = e.press_args() {
+            self.action_mode = match self.action_mode {
+                ActionMode::Reveal => ActionMode::Flag,
+                ActionMode::Flag => ActionMode::Reveal,
+            };
+        }
+
+        if let Some(Button::Keyboard(Key::F)) = e.press_args() {
+            self.gameboard.flag_all_obvious_mines();
+        }
+
+        // Space acts on the hovered cell, or on the selected one when the
+        // cursor is out of the board.
+        if let Some(Button::Keyboard(Key::Space)) = e.press_args() {
+            if let Some(ind) = self.hovered_cell.or(self.selected_cell) {
+                self.selected_cell = Some(ind);
+                match self.action_mode {
+                    ActionMode::Reveal => {
+                        self.gameboard.set(ind, PlayerCell::Revealed);
+                    },
+                    ActionMode::Flag => {
+                        self.gameboard.toggle_flag(ind[0], ind[1]);
+                    },
+                }
+            }
+        }
+
+        // S marks the hovered or selected cell as suspected.
+        if let Some(Button::Keyboard(Key::S)) = e.press_args() {
+            if let Some(ind) = self.hovered_cell.or(self.selected_cell) {
+                self.selected_cell = Some(ind);
+                self.gameboard.toggle_suspected(ind[0], ind[1]);
+            }
+        }
+
+        if let Some(Button::Mouse(MouseButton::Middle)) = e.press_args() {
             self.find_selected_cell(pos, cell_size);
-            match self.selected_cell {
-                Some(ind) => {
-                    let cell = self.gameboard.get_cell(ind[0], ind[1]);
-                    let val = match cell.get_player_cell() {
-                        PlayerCell::NotDetermined => PlayerCell::Flagged,
-                        PlayerCell::Flagged => PlayerCell::Question,
-                        PlayerCell::Question => PlayerCell::NotDetermined,
-                        _ => return,
-                    };
-                    self.gameboard.set(ind, val);
-                },
-                None => {},
+            if let Some(ind) = self.selected_cell {
+                self.gameboard.chord(ind[0], ind[1]);
+            }
+        }
+
+        if let Some(Button::Mouse(MouseButton::Right)) = e.press_args() {
+            if let Some(ind) = self.find_selected_unrevealed_cell(pos, cell_size) {
+                let cell = self.gameboard.get_cell(ind[0], ind[1]);
+                let val = match (self.flag_mode, cell.get_player_cell()) {
+                    (_, PlayerCell::NotDetermined) | (_, PlayerCell::Suspected) => PlayerCell::Flagged,
+                    (FlagMode::Cycle, PlayerCell::Flagged) => PlayerCell::Question,
+                    (FlagMode::Cycle, PlayerCell::Question) => PlayerCell::NotDetermined,
+                    (FlagMode::FlagOnly, PlayerCell::Flagged) => PlayerCell::NotDetermined,
+                    (FlagMode::FlagOnly, PlayerCell::Question) => PlayerCell::Flagged,
+                    _ => return,
+                };
+                self.gameboard.set(ind, val);
             }
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use piston::{ButtonArgs, ButtonState, Event, Input, Motion};
+
+    use super::*;
+
+    /// Where the board is drawn and the size of its cells in the tests.
+    const POS: [f64; 2] = [10.0, 100.0];
+    const CELL_SIZE: [f64; 2] = [20.0, 30.0];
+
+    /// Feeds an input event to the controller.
+    fn send(controller: &mut GameboardController, input: Input) {
+        controller.event(POS, CELL_SIZE, &Event::from(input));
+    }
+
+    /// Moves the mouse cursor over the given cell.
+    fn move_to(controller: &mut GameboardController, ind: [usize; 2]) {
+        let cursor = [
+            POS[0] + (ind[0] as f64 + 0.5) * CELL_SIZE[0],
+            POS[1] + (ind[1] as f64 + 0.5) * CELL_SIZE[1],
+        ];
+        send(controller, Input::Move(Motion::MouseCursor(cursor)));
+    }
+
+    #[test]
+    fn finds_the_cell_under_the_cursor() {
+        let mut controller = GameboardController::new(Gameboard::new([4, 4], 2));
+        controller.cursor_pos = [10.0, 100.0];
+        assert_eq!(controller.cell_at(POS, CELL_SIZE), Some([0, 0]));
+        controller.cursor_pos = [89.0, 219.0];
+        assert_eq!(controller.cell_at(POS, CELL_SIZE), Some([3, 3]));
+        controller.cursor_pos = [90.0, 150.0];
+        assert_eq!(controller.cell_at(POS, CELL_SIZE), None);
+        controller.cursor_pos = [50.0, 220.0];
+        assert_eq!(controller.cell_at(POS, CELL_SIZE), None);
+        controller.cursor_pos = [9.0, 150.0];
+        assert_eq!(controller.cell_at(POS, CELL_SIZE), None);
+    }
+
+    /// Presses and releases a key.
+    fn press_key(controller: &mut GameboardController, key: Key) {
+        for state in [ButtonState::Press, ButtonState::Release] {
+            let args = ButtonArgs { state, button: Button::Keyboard(key), scancode: None };
+            send(controller, Input::Button(args));
+        }
+    }
+
+    #[test]
+    fn space_applies_the_action_mode_to_the_cell_under_the_cursor() {
+        let mut controller = GameboardController::new(Gameboard::new_seeded([4, 4], 2, 1));
+        move_to(&mut controller, [0, 0]);
+        press_key(&mut controller, Key::Space);
+        assert_eq!(player_cell(&controller, [0, 0]), PlayerCell::Revealed);
+        let hidden = (0..16).map(|i| [i % 4, i / 4])
+            .find(|&ind| player_cell(&controller, ind) == PlayerCell::NotDetermined)
+            .unwrap();
+
+        press_key(&mut controller, Key::M);
+        assert_eq!(controller.action_mode, ActionMode::Flag);
+        move_to(&mut controller, hidden);
+        press_key(&mut controller, Key::Space);
+        assert_eq!(player_cell(&controller, hidden), PlayerCell::Flagged);
+        assert_eq!(controller.selected_cell, Some(hidden));
+
+        // Out of the board, space acts on the selected cell.
+        send(&mut controller, Input::Move(Motion::MouseCursor([0.0, 0.0])));
+        press_key(&mut controller, Key::Space);
+        assert_eq!(player_cell(&controller, hidden), PlayerCell::NotDetermined);
+
+        press_key(&mut controller, Key::M);
+        assert_eq!(controller.action_mode, ActionMode::Reveal);
+    }
+
+    #[test]
+    fn tracks_the_hovered_cell() {
+        let mut controller = GameboardController::new(Gameboard::new([4, 4], 2));
+        assert_eq!(controller.hovered_cell, None);
+        move_to(&mut controller, [2, 1]);
+        assert_eq!(controller.hovered_cell, Some([2, 1]));
+        send(&mut controller, Input::Move(Motion::MouseCursor([0.0, 0.0])));
+        assert_eq!(controller.hovered_cell, None);
+        move_to(&mut controller, [3, 3]);
+        send(&mut controller, Input::Cursor(false));
+        assert_eq!(controller.hovered_cell, None);
+        // Hovering does not select.
+        assert_eq!(controller.selected_cell, None);
+    }
+
+    /// Presses and releases a mouse button over the given cell.
+    fn click(controller: &mut GameboardController, ind: [usize; 2], button: MouseButton) {
+        move_to(controller, ind);
+        for state in [ButtonState::Press, ButtonState::Release] {
+            let args = ButtonArgs { state, button: Button::Mouse(button), scancode: None };
+            send(controller, Input::Button(args));
+        }
+    }
+
+    /// Gets what the player sees of a cell.
+    fn player_cell(controller: &GameboardController, ind: [usize; 2]) -> PlayerCell {
+        controller.gameboard.get_cell(ind[0], ind[1]).get_player_cell()
+    }
+
+    #[test]
+    fn right_click_cycles_through_the_flag_mode() {
+        let mut controller = GameboardController::new(Gameboard::new_seeded([4, 4], 2, 1));
+        click(&mut controller, [0, 0], MouseButton::Left);
+        let ind = (0..16).map(|i| [i % 4, i / 4])
+            .find(|&ind| player_cell(&controller, ind) == PlayerCell::NotDetermined)
+            .unwrap();
+
+        let mut seen = Vec::new();
+        for _ in 0..3 {
+            click(&mut controller, ind, MouseButton::Right);
+            seen.push(player_cell(&controller, ind));
+        }
+        assert_eq!(seen, [PlayerCell::Flagged, PlayerCell::Question, PlayerCell::NotDetermined]);
+
+        controller.flag_mode = FlagMode::FlagOnly;
+        seen.clear();
+        for _ in 0..3 {
+            click(&mut controller, ind, MouseButton::Right);
+            seen.push(player_cell(&controller, ind));
+        }
+        assert_eq!(seen, [PlayerCell::Flagged, PlayerCell::NotDetermined, PlayerCell::Flagged]);
+    }
+
+    #[test]
+    fn clicks_on_revealed_cells_keep_the_selection() {
+        let mut controller = GameboardController::new(Gameboard::new_seeded([4, 4], 2, 1));
+        click(&mut controller, [0, 0], MouseButton::Left);
+        let hidden = (0..16).map(|i| [i % 4, i / 4])
+            .find(|&ind| player_cell(&controller, ind) == PlayerCell::NotDetermined)
+            .unwrap();
+        click(&mut controller, hidden, MouseButton::Right);
+        assert_eq!(controller.selected_cell, Some(hidden));
+        assert_eq!(player_cell(&controller, hidden), PlayerCell::Flagged);
+
+        // Neither button moves the selection away from the flagged cell.
+        for button in [MouseButton::Left, MouseButton::Right] {
+            click(&mut controller, [0, 0], button);
+            assert_eq!(controller.selected_cell, Some(hidden));
+            assert_eq!(controller.hovered_cell, Some([0, 0]));
+            assert_eq!(player_cell(&controller, [0, 0]), PlayerCell::Revealed);
+        }
+    }
+
+    /// Presses or releases the left button.
+    fn left_button(controller: &mut GameboardController, state: ButtonState) {
+        let args = ButtonArgs { state, button: Button::Mouse(MouseButton::Left), scancode: None };
+        send(controller, Input::Button(args));
+    }
+
+    #[test]
+    fn short_presses_reveal_and_long_presses_flag() {
+        let mut controller = GameboardController::new(Gameboard::new_seeded([4, 4], 2, 1));
+        controller.long_press_threshold = Some(Duration::from_secs(3600));
+        move_to(&mut controller, [0, 0]);
+        left_button(&mut controller, ButtonState::Press);
+        // Nothing happens until the button is released.
+        assert_eq!(player_cell(&controller, [0, 0]), PlayerCell::NotDetermined);
+        left_button(&mut controller, ButtonState::Release);
+        assert_eq!(player_cell(&controller, [0, 0]), PlayerCell::Revealed);
+
+        let hidden = (0..16).map(|i| [i % 4, i / 4])
+            .find(|&ind| player_cell(&controller, ind) == PlayerCell::NotDetermined)
+            .unwrap();
+        // Every press lasts long enough.
+        controller.long_press_threshold = Some(Duration::from_secs(0));
+        move_to(&mut controller, hidden);
+        left_button(&mut controller, ButtonState::Press);
+        left_button(&mut controller, ButtonState::Release);
+        assert_eq!(player_cell(&controller, hidden), PlayerCell::Flagged);
+        left_button(&mut controller, ButtonState::Press);
+        left_button(&mut controller, ButtonState::Release);
+        assert_eq!(player_cell(&controller, hidden), PlayerCell::NotDetermined);
+    }
+
+    #[test]
+    fn presses_reveal_at_once_by_default() {
+        let mut controller = GameboardController::new(Gameboard::new_seeded([4, 4], 2, 1));
+        move_to(&mut controller, [0, 0]);
+        left_button(&mut controller, ButtonState::Press);
+        assert_eq!(player_cell(&controller, [0, 0]), PlayerCell::Revealed);
+    }
+
+    #[test]
+    fn f_flags_the_obvious_mines() {
+        let mut controller = GameboardController::new(Gameboard::from_ascii_with_player_state("1*\n11\n").unwrap());
+        press_key(&mut controller, Key::F);
+        assert_eq!(player_cell(&controller, [1, 0]), PlayerCell::Flagged);
+    }
+
+    #[test]
+    fn s_marks_suspected_cells_that_right_clicks_flag() {
+        let mut controller = GameboardController::new(Gameboard::from_ascii_with_player_state("1*.\n11.\n").unwrap());
+        move_to(&mut controller, [1, 0]);
+        press_key(&mut controller, Key::S);
+        assert_eq!(player_cell(&controller, [1, 0]), PlayerCell::Suspected);
+        assert_eq!(controller.selected_cell, Some([1, 0]));
+        click(&mut controller, [1, 0], MouseButton::Right);
+        assert_eq!(player_cell(&controller, [1, 0]), PlayerCell::Flagged);
+    }
+}
@@ -1,8 +1,11 @@
 //! Gameboard view.
 
-use graphics::{CharacterCache, Context, Graphics, Image, Line, Rectangle, Text, Transformed, types::Color};
+use std::{cmp::min, path::Path};
 
-use crate::GameboardController;
+use graphics::{CharacterCache, character::Character, Context, Graphics, Image, Line, Rectangle, Text, Transformed, types::Color};
+use image::{Rgba, RgbaImage};
+
+use crate::{Gameboard, GameboardController, RsweeperError, Theme, gameboard::CellDisplay, gameboard_controller::ActionMode};
 
 /// Stores gameboard view settings.
 pub struct GameboardViewSettings {
@@ -34,6 +37,28 @@ pub struct GameboardViewSettings {
     pub selected_cell_background_color: Color,
     /// Text color.
     pub text_color: Color,
+    /// Color of the progress bar drawn below the board.
+    pub progress_bar_color: Color,
+    /// Height of the progress bar drawn below the board.
+    pub progress_bar_height: f64,
+    /// Color a cell flashes with when it gets revealed.
+    pub reveal_highlight_color: Color,
+    /// Duration in seconds of the flash of revealed cells, 0.0 to disable it.
+    pub reveal_fade_duration: f64,
+    /// Whether wrongly flagged cells shake when the game is lost.
+    pub wrong_flag_shake: bool,
+    /// Whether column indices are drawn above the board and row indices on
+    /// its left, which requires some room around `gameboard_position`.
+    pub show_coordinates: bool,
+    /// Whether numbers having as many flagged neighbors as their value are
+    /// drawn lighter, to focus on the others.
+    pub dim_satisfied_numbers: bool,
+    /// Whether numbers are drawn with a dark outline, for readability.
+    pub high_contrast: bool,
+    /// Color of the outline drawn around numbers in high contrast mode.
+    pub outline_color: Color,
+    /// Width in pixels of the outline drawn around numbers.
+    pub outline_width: f64,
 }
 
 impl GameboardViewSettings {
@@ -41,24 +66,178 @@ impl GameboardViewSettings {
     pub fn new(gameboard_size: [usize; 2]) -> Self {
         let cell_size = [30.0; 2];
         let bombs_left_x =  (gameboard_size[0] as f64 * cell_size[0]) - 150.0;
+        let theme = Theme::default();
         Self {
             gameboard_position: [10.0, 100.0],
             bombs_left_position: [bombs_left_x, 60.0],
             cell_size: [30.0, 30.0],
-            background_color: [0.8, 0.8, 1.0, 1.0],
-            border_color: [0.0, 0.0, 0.2, 1.0],
-            board_edge_color: [0.0, 0.0, 0.2, 1.0],
-            section_edge_color: [0.0, 0.0, 0.2, 1.0],
-            cell_edge_color: [0.0, 0.0, 0.2, 1.0],
+            background_color: theme.background_color,
+            border_color: theme.border_color,
+            board_edge_color: theme.board_edge_color,
+            section_edge_color: theme.section_edge_color,
+            cell_edge_color: theme.cell_edge_color,
             board_edge_radius: 3.0,
             section_edge_radius: 2.0,
             cell_edge_radius: 1.0,
-            selected_cell_background_color: [0.9, 0.9, 1.0, 1.0],
-            text_color: [0.0, 0.0, 0.1, 1.0],
+            selected_cell_background_color: theme.selected_cell_background_color,
+            text_color: theme.text_color,
+            progress_bar_color: theme.progress_bar_color,
+            progress_bar_height: 10.0,
+            reveal_highlight_color: theme.reveal_highlight_color,
+            reveal_fade_duration: 0.3,
+            wrong_flag_shake: true,
+            show_coordinates: false,
+            dim_satisfied_numbers: true,
+            high_contrast: false,
+            outline_color: [0.0, 0.0, 0.0, 1.0],
+            outline_width: 1.0,
+        }
+    }
+
+    /// Creates new gameboard view settings with the colors of the theme file
+    /// at `path`.
+    pub fn load_theme<P: AsRef<Path>>(path: P, gameboard_size: [usize; 2]) -> Result<Self, RsweeperError> {
+        let mut settings = Self::new(gameboard_size);
+        settings.apply_theme(&Theme::load(path)?);
+        Ok(settings)
+    }
+
+    /// Replaces the colors by the ones of `theme`.
+    pub fn apply_theme(&mut self, theme: &Theme) {
+        self.background_color = theme.background_color;
+        self.border_color = theme.border_color;
+        self.board_edge_color = theme.board_edge_color;
+        self.section_edge_color = theme.section_edge_color;
+        self.cell_edge_color = theme.cell_edge_color;
+        self.selected_cell_background_color = theme.selected_cell_background_color;
+        self.text_color = theme.text_color;
+        self.progress_bar_color = theme.progress_bar_color;
+        self.reveal_highlight_color = theme.reveal_highlight_color;
+    }
+
+    /// Returns the current colors as a theme.
+    pub fn theme(&self) -> Theme {
+        Theme {
+            background_color: self.background_color,
+            border_color: self.border_color,
+            board_edge_color: self.board_edge_color,
+            section_edge_color: self.section_edge_color,
+            cell_edge_color: self.cell_edge_color,
+            selected_cell_background_color: self.selected_cell_background_color,
+            text_color: self.text_color,
+            progress_bar_color: self.progress_bar_color,
+            reveal_highlight_color: self.reveal_highlight_color,
         }
     }
 }
 
+/// Opacity of the numbers dimmed by `dim_satisfied_numbers`.
+const SATISFIED_NUMBER_ALPHA: f32 = 0.4;
+
+/// Color of the bombs left counter when there are more flags than bombs.
+const OVER_FLAGGED_COLOR: Color = [0.9, 0.0, 0.0, 1.0];
+
+/// Font size of the coordinate labels.
+const COORDINATE_FONT_SIZE: u32 = 12;
+
+/// Blends `base` toward `highlight`, fully highlighted when `elapsed` is zero
+/// and back to `base` once `duration` seconds elapsed.
+pub fn reveal_fade_color(base: Color, highlight: Color, elapsed: f64, duration: f64) -> Color {
+    if duration <= 0.0 || elapsed >= duration {
+        return base;
+    }
+    let t = (1.0 - elapsed / duration) as f32;
+    let mut res = base;
+    for (c, h) in res.iter_mut().zip(&highlight) {
+        *c += (h - *c) * t;
+    }
+    res
+}
+
+/// Duration in seconds of the shake of wrongly flagged cells.
+const SHAKE_DURATION: f64 = 0.5;
+
+/// Gets the horizontal offset of a shaking cell, `elapsed` seconds after the
+/// game was lost. It is a decaying sine, zero after `SHAKE_DURATION`.
+pub fn shake_offset(elapsed: f64) -> f64 {
+    if !(0.0..SHAKE_DURATION).contains(&elapsed) {
+        return 0.0;
+    }
+    let amplitude = 3.0 * (1.0 - elapsed / SHAKE_DURATION);
+    amplitude * (2.0 * std::f64::consts::PI * 20.0 * elapsed).sin()
+}
+
+/// Gets the width of the filled part of a progress bar `full_width` pixels
+/// wide, once `fraction` of it is done. The fraction is clamped between 0.0
+/// and 1.0, and an undefined one gives an empty bar.
+pub fn progress_bar_width(fraction: f64, full_width: f64) -> f64 {
+    if fraction.is_nan() {
+        return 0.0;
+    }
+    full_width * fraction.clamp(0.0, 1.0)
+}
+
+/// Offsets at which the outline of a glyph is drawn, `width` pixels away
+/// from it in each of the four directions.
+pub fn outline_offsets(width: f64) -> [[f64; 2]; 4] {
+    [[-width, 0.0], [width, 0.0], [0.0, -width], [0.0, width]]
+}
+
+/// Draws `character` in `color` with its top-left corner at `pos`.
+fn draw_glyph<G: Graphics>(character: &Character<G::Texture>, color: Color, pos: [f64; 2], c: &Context, g: &mut G) {
+    Image::new_color(color)
+        .src_rect([
+            character.atlas_offset[0],
+            character.atlas_offset[1],
+            character.atlas_size[0],
+            character.atlas_size[1],
+        ])
+        .draw(character.texture, &c.draw_state, c.transform.trans(pos[0], pos[1]), g);
+}
+
+/// Converts a color to an 8-bit RGBA pixel.
+fn to_pixel(color: Color) -> Rgba<u8> {
+    let mut res = [0; 4];
+    for (p, c) in res.iter_mut().zip(&color) {
+        *p = (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    }
+    Rgba(res)
+}
+
+/// Width and height of the characters of `pixel_glyph`.
+const PIXEL_GLYPH_SIZE: [u32; 2] = [3, 5];
+
+/// Gets the rows of a small pixel font character, the most significant of
+/// the 3 bits of each row being its left pixel, for the characters cells show.
+fn pixel_glyph(ch: char) -> Option<[u8; 5]> {
+    let rows = match ch {
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b110, 0b001, 0b010, 0b100, 0b111],
+        '3' => [0b110, 0b001, 0b110, 0b001, 0b110],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b110, 0b001, 0b110],
+        '6' => [0b011, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        '?' => [0b110, 0b001, 0b010, 0b000, 0b010],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        _ => return None,
+    };
+    Some(rows)
+}
+
+/// Draws `over` on top of the opaque `base`, according to its alpha.
+fn blend(base: Color, over: Color) -> Color {
+    let mut res = base;
+    for (c, o) in res.iter_mut().zip(&over).take(3) {
+        *c += (o - *c) * over[3];
+    }
+    res
+}
+
 /// Stores visual informatin about a gameboard.
 pub struct GameboardView {
     /// Stores gameboard view settings.
@@ -73,6 +252,127 @@ impl GameboardView {
         }
     }
 
+    /// Gets the rectangle `[x, y, width, height]` of a cell, from the
+    /// left-top corner of the window.
+    pub fn cell_rect(&self, ind: [usize; 2]) -> [f64; 4] {
+        let settings = &self.settings;
+        [
+            settings.gameboard_position[0] + (ind[0] as f64) * settings.cell_size[0],
+            settings.gameboard_position[1] + (ind[1] as f64) * settings.cell_size[1],
+            settings.cell_size[0], settings.cell_size[1],
+        ]
+    }
+
+    /// Gets the character with its own color and the background color of a
+    /// cell, without any animation.
+    fn cell_display(&self, gameboard: &Gameboard, ind: [usize; 2]) -> CellDisplay {
+        let (mut ch, bg_color) = gameboard.char_and_colors(ind);
+        if self.settings.dim_satisfied_numbers && gameboard.is_number_satisfied(ind[0], ind[1]) {
+            if let Some((_, ref mut ch_color)) = ch {
+                ch_color[3] *= SATISFIED_NUMBER_ALPHA;
+            }
+        }
+        (ch, bg_color)
+    }
+
+    /// Gets the character with its own color and the background color of a
+    /// cell, as they should be drawn now.
+    fn cell_colors(&self, gameboard: &Gameboard, ind: [usize; 2]) -> CellDisplay {
+        let (ch, mut bg_color) = self.cell_display(gameboard, ind);
+        if let Some(revealed_at) = gameboard.get_cell(ind[0], ind[1]).revealed_at() {
+            bg_color = reveal_fade_color(
+                bg_color,
+                self.settings.reveal_highlight_color,
+                revealed_at.elapsed().as_secs_f64(),
+                self.settings.reveal_fade_duration,
+            );
+        }
+        (ch, bg_color)
+    }
+
+    /// Renders the gameboard into an image, e.g., to export it as a PNG
+    /// without any window. The image covers the board only, with one pixel
+    /// per unit of `cell_size`. Cells are rendered as in `draw` without any
+    /// animation, and their characters with a small pixel font, as there is
+    /// no font rasterizer outside of a graphics context.
+    pub fn render_image(&self, gameboard: &Gameboard) -> RgbaImage {
+        let settings = &self.settings;
+        let width = (settings.cell_size[0] * (gameboard.size[0] as f64)).round() as u32;
+        let height = (settings.cell_size[1] * (gameboard.size[1] as f64)).round() as u32;
+        let mut img = RgbaImage::from_pixel(width, height, to_pixel(settings.background_color));
+        let edge = to_pixel(settings.cell_edge_color);
+
+        for cell_y in 0..gameboard.size[1] {
+            for cell_x in 0..gameboard.size[0] {
+                let (ch, bg_color) = self.cell_display(gameboard, [cell_x, cell_y]);
+                let bg = to_pixel(bg_color);
+                let rect = self.cell_rect([cell_x, cell_y]);
+                let x0 = (rect[0] - settings.gameboard_position[0]).round() as u32;
+                let y0 = (rect[1] - settings.gameboard_position[1]).round() as u32;
+                let x1 = min(x0 + rect[2].round() as u32, width);
+                let y1 = min(y0 + rect[3].round() as u32, height);
+                for y in y0..y1 {
+                    for x in x0..x1 {
+                        // Cell edges are on the left and top sides.
+                        let pixel = if x == x0 || y == y0 { edge } else { bg };
+                        img.put_pixel(x, y, pixel);
+                    }
+                }
+
+                // If there is a char, draw it scaled in the middle of the cell.
+                if let Some((bitmap, ch_color)) = ch.and_then(|(ch, color)| Some((pixel_glyph(ch)?, color))) {
+                    let scale = min((x1 - x0) / (PIXEL_GLYPH_SIZE[0] + 2), (y1 - y0) / (PIXEL_GLYPH_SIZE[1] + 2)).max(1);
+                    let gx = x0 + (x1 - x0).saturating_sub(scale * PIXEL_GLYPH_SIZE[0]) / 2;
+                    let gy = y0 + (y1 - y0).saturating_sub(scale * PIXEL_GLYPH_SIZE[1]) / 2;
+                    let fg = to_pixel(blend(bg_color, ch_color));
+                    for (row, bits) in bitmap.iter().enumerate() {
+                        for col in 0..PIXEL_GLYPH_SIZE[0] {
+                            if bits & (1 << (PIXEL_GLYPH_SIZE[0] - 1 - col)) == 0 {
+                                continue;
+                            }
+                            for y in gy + row as u32 * scale..gy + (row as u32 + 1) * scale {
+                                for x in gx + col * scale..gx + (col + 1) * scale {
+                                    if x < x1 && y < y1 {
+                                        img.put_pixel(x, y, fg);
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // Draw board edge.
+        let board_edge = to_pixel(settings.board_edge_color);
+        for x in 0..width {
+            img.put_pixel(x, 0, board_edge);
+            img.put_pixel(x, height - 1, board_edge);
+        }
+        for y in 0..height {
+            img.put_pixel(0, y, board_edge);
+            img.put_pixel(width - 1, y, board_edge);
+        }
+        img
+    }
+
+    /// Returns the text positions of the column labels, centered above each
+    /// column, and of the row labels, on the left of each row, for a board
+    /// of `gameboard_size` cells. Labels are kept inside the window when the
+    /// board is too close to its edges.
+    pub fn coordinate_label_positions(&self, gameboard_size: [usize; 2]) -> (Vec<[f64; 2]>, Vec<[f64; 2]>) {
+        let settings = &self.settings;
+        let [left, top] = settings.gameboard_position;
+        let font_size = COORDINATE_FONT_SIZE as f64;
+        let columns = (0..gameboard_size[0])
+            .map(|x| [left + (x as f64 + 0.5) * settings.cell_size[0] - font_size / 4.0, (top - font_size / 2.0).max(font_size)])
+            .collect();
+        let rows = (0..gameboard_size[1])
+            .map(|y| [(left - 2.0 * font_size).max(0.0), top + (y as f64 + 0.5) * settings.cell_size[1] + font_size / 2.0])
+            .collect();
+        (columns, rows)
+    }
+
     /// Draw the gameboard.
     pub fn draw<G: Graphics, C>(
         &self,
@@ -84,8 +384,8 @@ impl GameboardView {
     where
         C: CharacterCache<Texture=G::Texture>,
     {
-        let ref settings = self.settings;
-        let ref gameboard = controller.gameboard;
+        let settings = &self.settings;
+        let gameboard = &controller.gameboard;
         let gameboard_size = [
             settings.cell_size[0] * (gameboard.size[0] as f64),
             settings.cell_size[1] * (gameboard.size[1] as f64),
@@ -95,17 +395,24 @@ impl GameboardView {
             gameboard_size[0], gameboard_size[1],
         ];
 
-        let text = Text::new(30);
+        // Warn the player when there are more flags than bombs.
+        let text = if gameboard.flag_balance() > 0 {
+            Text::new_color(OVER_FLAGGED_COLOR, 30)
+        } else {
+            Text::new(30)
+        };
         let bombs_counter_rect = [
             settings.bombs_left_position[0], settings.bombs_left_position[1],
             140.0, 140.0,
         ];
 
         // Draw bomb counters.
-        let str = match gameboard.state {
-            crate::GameState::Lost => format!("BOOM!"),
-            crate::GameState::Won => format!("You won!"),
-            _ => format!("Left: {}", gameboard.bombs - gameboard.flagged),
+        let str = if gameboard.is_lost() {
+            "BOOM!".to_string()
+        } else if gameboard.is_won() {
+            "You won!".to_string()
+        } else {
+            format!("Left: {}", -gameboard.flag_balance())
         };
 
         let _ = text.draw(&str, glyphs, &c.draw_state, c.transform.trans(bombs_counter_rect[0], bombs_counter_rect[1]), g);
@@ -118,14 +425,24 @@ impl GameboardView {
         let flag_pole = Line::new([0.65, 0.16, 0.16, 1.00], 2.0);
         let flag = Line::new([1.00, 0.00, 0.00, 1.00], 1.0);
 
-        let x_size = gameboard_size[0] / (gameboard.size[0] as f64);
-        let y_size = gameboard_size[1] / (gameboard.size[1] as f64);
+        // The cells a chord on the hovered cell would reveal.
+        let chord_preview = controller.hovered_cell
+            .and_then(|ind| gameboard.preview_chord(ind[0], ind[1]))
+            .unwrap_or_default();
         for cell_y in 0..gameboard.size[1] {
             for cell_x in 0..gameboard.size[0] {
-                let (ch, bg_color) = gameboard.char_and_colors([cell_x, cell_y]);
+                let (ch, mut bg_color) = self.cell_colors(gameboard, [cell_x, cell_y]);
+                if controller.hovered_cell == Some([cell_x, cell_y])
+                    || chord_preview.contains(&(cell_x, cell_y))
+                {
+                    bg_color = settings.selected_cell_background_color;
+                }
 
-                let x = settings.gameboard_position[0] + (cell_x as f64) * x_size;
-                let y = settings.gameboard_position[1] + (cell_y as f64) * y_size;
+                let [mut x, y, x_size, y_size] = self.cell_rect([cell_x, cell_y]);
+                // Shake the wrongly flagged cells to draw attention.
+                if let (Some(('X', _)), Some(lost_at), true) = (ch, gameboard.lost_at(), settings.wrong_flag_shake) {
+                    x += shake_offset(lost_at.elapsed().as_secs_f64());
+                }
                 let x2 = x + x_size;
                 let y2 = y + y_size;
 
@@ -146,7 +463,6 @@ impl GameboardView {
 
                 // If there is a char, draw it.
                 if let Some((ch, ch_color)) = ch {
-                    let text_image = Image::new_color(ch_color);
                     let pos = [
                         x,
                         y,
@@ -170,25 +486,185 @@ impl GameboardView {
                             _ => {
                                 let ch_x = pos[0] + (settings.cell_size[0] - character.atlas_size[0]) / 2.0;
                                 let ch_y = pos[1] + (settings.cell_size[1] - character.atlas_size[1]) / 2.0;
-                                let text_image = text_image.src_rect([
-                                    character.atlas_offset[0],
-                                    character.atlas_offset[1],
-                                    character.atlas_size[0],
-                                    character.atlas_size[1],
-                                ]);
-                                text_image.draw(character.texture,
-                                                &c.draw_state,
-                                                c.transform.trans(ch_x, ch_y),
-                                                g);
+                                if settings.high_contrast {
+                                    for offset in outline_offsets(settings.outline_width).iter() {
+                                        draw_glyph(&character, settings.outline_color, [ch_x + offset[0], ch_y + offset[1]], c, g);
                                     }
+                                }
+                                draw_glyph(&character, ch_color, [ch_x, ch_y], c, g);
+                            },
                         }
                     }
                 }
             }
         }
 
+        // Draw coordinate labels.
+        if settings.show_coordinates {
+            let label = Text::new_color(settings.text_color, COORDINATE_FONT_SIZE);
+            let (columns, rows) = self.coordinate_label_positions(gameboard.size);
+            for (x, pos) in columns.iter().enumerate() {
+                let _ = label.draw(&x.to_string(), glyphs, &c.draw_state, c.transform.trans(pos[0], pos[1]), g);
+            }
+            for (y, pos) in rows.iter().enumerate() {
+                let _ = label.draw(&y.to_string(), glyphs, &c.draw_state, c.transform.trans(pos[0], pos[1]), g);
+            }
+        }
+
         // Draw board edge.
         Rectangle::new_border(settings.board_edge_color, settings.board_edge_radius)
             .draw(board_rect, &c.draw_state, c.transform, g);
+
+        // Draw the progress bar in the footer, its width scales with the
+        // fraction of safe cells revealed.
+        let progress_y = board_rect[1] + board_rect[3] + 10.0;
+        let progress_rect = [
+            board_rect[0], progress_y,
+            progress_bar_width(gameboard.progress_fraction(), board_rect[2]), settings.progress_bar_height,
+        ];
+        Rectangle::new(settings.progress_bar_color)
+            .draw(progress_rect, &c.draw_state, c.transform, g);
+        let progress_border_rect = [
+            board_rect[0], progress_y,
+            board_rect[2], settings.progress_bar_height,
+        ];
+        Rectangle::new_border(settings.board_edge_color, settings.cell_edge_radius)
+            .draw(progress_border_rect, &c.draw_state, c.transform, g);
+
+        // Draw how many reveals are left below the progress bar.
+        let cells_left = format!("To reveal: {}", gameboard.cells_left_to_win());
+        let cells_left_y = progress_y + settings.progress_bar_height + 20.0;
+        let _ = Text::new_color(settings.text_color, 16)
+            .draw(&cells_left, glyphs, &c.draw_state, c.transform.trans(board_rect[0], cells_left_y), g);
+
+        // Draw what the space key does.
+        let mode = match controller.action_mode {
+            ActionMode::Reveal => "Mode: reveal (M)",
+            ActionMode::Flag => "Mode: flag (M)",
+        };
+        let _ = Text::new_color(settings.text_color, 16)
+            .draw(mode, glyphs, &c.draw_state, c.transform.trans(board_rect[0], cells_left_y + 20.0), g);
+        let _ = Text::new_color(settings.text_color, 16)
+            .draw("Flag obvious mines (F)", glyphs, &c.draw_state, c.transform.trans(board_rect[0], cells_left_y + 40.0), g);
+
+        // Show the numbers uncovered once the game is won.
+        if gameboard.is_won() {
+            let counts: Vec<String> = gameboard.number_histogram().iter().enumerate()
+                .skip(1)
+                .map(|(n, count)| format!("{}: {}", n, count))
+                .collect();
+            let numbers = format!("Numbers revealed - {}", counts.join(", "));
+            let _ = Text::new_color(settings.text_color, 16)
+                .draw(&numbers, glyphs, &c.draw_state, c.transform.trans(board_rect[0], cells_left_y + 60.0), g);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Gets the pixels of the inside of a cell, without its edges.
+    fn cell_pixels(img: &RgbaImage, ind: [u32; 2], cell_size: u32) -> Vec<Rgba<u8>> {
+        let (x0, y0) = (ind[0] * cell_size, ind[1] * cell_size);
+        (y0 + 1..y0 + cell_size - 1)
+            .flat_map(|y| (x0 + 1..x0 + cell_size - 1).map(move |x| *img.get_pixel(x, y)))
+            .collect()
+    }
+
+    #[test]
+    fn reveal_fade_goes_back_to_the_base_color() {
+        let base = [0.0, 0.0, 0.0, 1.0];
+        let highlight = [1.0, 0.5, 0.0, 1.0];
+        assert_eq!(reveal_fade_color(base, highlight, 0.0, 0.4), highlight);
+        assert_eq!(reveal_fade_color(base, highlight, 0.2, 0.4), [0.5, 0.25, 0.0, 1.0]);
+        assert_eq!(reveal_fade_color(base, highlight, 0.4, 0.4), base);
+        assert_eq!(reveal_fade_color(base, highlight, 0.0, 0.0), base);
+    }
+
+    #[test]
+    fn shake_decays() {
+        assert_eq!(shake_offset(-0.1), 0.0);
+        assert_eq!(shake_offset(0.0), 0.0);
+        assert!((shake_offset(0.0125) - 2.925).abs() < 1e-9);
+        assert!(shake_offset(0.4125).abs() < shake_offset(0.0125).abs());
+        assert_eq!(shake_offset(SHAKE_DURATION), 0.0);
+    }
+
+    #[test]
+    fn progress_bar_scales_with_the_revealed_cells() {
+        assert_eq!(progress_bar_width(0.0, 200.0), 0.0);
+        assert_eq!(progress_bar_width(0.25, 200.0), 50.0);
+        assert_eq!(progress_bar_width(1.0, 200.0), 200.0);
+        assert_eq!(progress_bar_width(f64::NAN, 200.0), 0.0);
+
+        // Before the bombs are placed.
+        let mut gameboard = Gameboard::new_seeded([9, 9], 10, 2);
+        assert_eq!(progress_bar_width(gameboard.progress_fraction(), 200.0), 0.0);
+        gameboard.set([4, 4], crate::gameboard::PlayerCell::Revealed);
+        let width = progress_bar_width(gameboard.progress_fraction(), 200.0);
+        assert!(width > 0.0 && width < 200.0);
+        let gameboard = Gameboard::from_ascii_with_player_state("1F\n11\n").unwrap();
+        assert_eq!(progress_bar_width(gameboard.progress_fraction(), 200.0), 200.0);
+    }
+
+    #[test]
+    fn pixels_are_clamped() {
+        assert_eq!(to_pixel([-1.0, 0.5, 2.0, 1.0]), Rgba([0, 128, 255, 255]));
+    }
+
+    #[test]
+    fn renders_the_cells_and_their_characters() {
+        let view = GameboardView::new(GameboardViewSettings::new([2, 2]));
+        let gameboard = Gameboard::from_ascii_with_player_state("1*\n11\n").unwrap();
+        let img = view.render_image(&gameboard);
+        assert_eq!(img.dimensions(), (60, 60));
+        assert_eq!(*img.get_pixel(0, 0), to_pixel(view.settings.board_edge_color));
+        assert_eq!(*img.get_pixel(30, 10), to_pixel(view.settings.cell_edge_color));
+
+        let rev_background = to_pixel(gameboard.char_and_colors([0, 0]).1);
+        let number = cell_pixels(&img, [0, 0], 30);
+        assert!(number.contains(&rev_background));
+        assert!(number.contains(&Rgba([0, 0, 255, 255])));
+        let hidden = cell_pixels(&img, [1, 0], 30);
+        assert!(hidden.iter().all(|&p| p == Rgba([255; 4])));
+
+        // Satisfied numbers are dimmed, without any fade animation.
+        let gameboard = Gameboard::from_ascii_with_player_state("1F\n11\n").unwrap();
+        let number = cell_pixels(&view.render_image(&gameboard), [0, 0], 30);
+        assert!(!number.contains(&Rgba([0, 0, 255, 255])));
+        assert!(number.iter().any(|&p| p != rev_background));
+    }
+
+    #[test]
+    fn coordinate_labels_are_outside_the_board() {
+        let view = GameboardView::new(GameboardViewSettings::new([4, 3]));
+        let [left, top] = view.settings.gameboard_position;
+        let (columns, rows) = view.coordinate_label_positions([4, 3]);
+        assert_eq!((columns.len(), rows.len()), (4, 3));
+        assert!(columns.iter().all(|pos| pos[1] < top));
+        assert!(columns.windows(2).all(|w| w[1][0] - w[0][0] == view.settings.cell_size[0]));
+        assert!(rows.iter().all(|pos| pos[0] < left));
+        assert!(rows.windows(2).all(|w| w[1][1] - w[0][1] == view.settings.cell_size[1]));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn coordinate_labels_stay_in_the_window() {
+        let mut settings = GameboardViewSettings::new([12, 12]);
+        settings.show_coordinates = true;
+        let view = GameboardView::new(settings);
+        let (columns, rows) = view.coordinate_label_positions([12, 12]);
+        assert!(columns.iter().chain(&rows).all(|pos| pos[0] >= 0.0 && pos[1] >= 0.0));
+
+        let mut settings = GameboardViewSettings::new([12, 12]);
+        settings.gameboard_position = [0.0, 0.0];
+        let view = GameboardView::new(settings);
+        let (columns, rows) = view.coordinate_label_positions([12, 12]);
+        assert!(columns.iter().chain(&rows).all(|pos| pos[0] >= 0.0 && pos[1] >= 0.0));
+    }
+
+    #[test]
+    fn outline_surrounds_the_glyph() {
+        assert_eq!(outline_offsets(2.0), [[-2.0, 0.0], [2.0, 0.0], [0.0, -2.0], [0.0, 2.0]]);
+    }
+}
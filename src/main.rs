@@ -1,11 +1,19 @@
 #![deny(missing_docs)]
 //! A sweeper game made in Rust.
 
+use std::io;
+
 use glutin_window::GlutinWindow;
 use graphics::clear;
 use piston::{EventLoop, EventSettings, Events, RenderEvent, window::WindowSettings};
 use opengl_graphics::{Filter, GlGraphics, GlyphCache, OpenGL, TextureSettings};
 
+/// Where the statistics of the games played are stored.
+const STATS_PATH: &str = "rsweeper_stats.json";
+/// Where the optional color theme is read from, unless the configuration
+/// gives another file.
+const THEME_PATH: &str = "rsweeper_theme.json";
+
 fn main() {
     let opengl = OpenGL::V3_2;
     let settings = WindowSettings::new("RSweeper", [1024;2])
@@ -14,22 +22,58 @@ fn main() {
     let mut window: GlutinWindow = settings.build()
         .expect("could not create window");
     
-    let mut events = Events::new(EventSettings::new().lazy(true));
     let mut gl = GlGraphics::new(opengl);
 
-    let gameboard_size = [10, 10];
-    let gameboard = Gameboard::new(gameboard_size, 10);
+    let config = GameConfig::load_or_default("rsweeper.toml");
+    let gameboard = config.build_board().expect("invalid configuration");
     let mut gameboard_controller = GameboardController::new(gameboard);
-    let gameboard_view_settings = GameboardViewSettings::new(gameboard_controller.gameboard.size);
+    let size = gameboard_controller.gameboard.size;
+    let theme_path = config.theme.as_deref().unwrap_or(THEME_PATH);
+    let gameboard_view_settings = match GameboardViewSettings::load_theme(theme_path, size) {
+        Ok(settings) => settings,
+        // The default theme file is optional.
+        Err(RsweeperError::Io(ref e)) if e.kind() == io::ErrorKind::NotFound && config.theme.is_none() => {
+            GameboardViewSettings::new(size)
+        },
+        Err(e) => {
+            println!("Ignoring {}: {}", theme_path, e);
+            GameboardViewSettings::new(size)
+        },
+    };
     let gameboard_view = GameboardView::new(gameboard_view_settings);
 
+    // Animations need continuous rendering.
+    let animated = gameboard_view.settings.reveal_fade_duration > 0.0
+        || gameboard_view.settings.wrong_flag_shake;
+    let mut events = Events::new(EventSettings::new().lazy(!animated));
+
     let texture_settings = TextureSettings::new().filter(Filter::Nearest);
-    let ref mut glyphs = GlyphCache::new("assets/FiraSans-Bold.ttf", (), texture_settings)
+    let glyphs = &mut GlyphCache::new("assets/FiraSans-Bold.ttf", (), texture_settings)
         .expect("cannot load font");
 
+    let mut stats = Stats::load_or_default(STATS_PATH).unwrap_or_else(|e| {
+        println!("Ignoring {}: {}", STATS_PATH, e);
+        Stats::default()
+    });
+    // Whether the result of the current game was already recorded.
+    let mut recorded = false;
+
     while let Some(e) = events.next(&mut window) {
         gameboard_controller.event(gameboard_view.settings.gameboard_position,
             gameboard_view.settings.cell_size, &e);
+        let gameboard = &gameboard_controller.gameboard;
+        match gameboard.state {
+            GameState::Won | GameState::Lost if !recorded => {
+                let won = matches!(gameboard.state, GameState::Won);
+                stats.record_result(gameboard.classify_difficulty(), won, gameboard.elapsed());
+                if let Err(e) = stats.save(STATS_PATH) {
+                    println!("Cannot save {}: {}", STATS_PATH, e);
+                }
+                recorded = true;
+            },
+            GameState::Initial | GameState::Alive => recorded = false,
+            _ => {},
+        }
         if let Some(args) = e.render_args() {
             gl.draw(args.viewport(), |c, g| {
                 clear([1.0; 4], g);
@@ -39,10 +83,22 @@ fn main() {
     }
 }
 
+pub use crate::config::GameConfig;
+pub use crate::error::RsweeperError;
 pub use crate::gameboard::{Gameboard, GameState};
 pub use crate::gameboard_controller::GameboardController;
 pub use crate::gameboard_view::{GameboardView, GameboardViewSettings};
+pub use crate::race::RaceGame;
+pub use crate::replay::Replay;
+pub use crate::stats::Stats;
+pub use crate::theme::Theme;
 
+mod config;
+mod error;
 mod gameboard;
 mod gameboard_controller;
-mod gameboard_view;
\ No newline at end of file
+mod gameboard_view;
+mod race;
+mod replay;
+mod stats;
+mod theme;
\ No newline at end of file
@@ -0,0 +1,123 @@
+//! Race mode, where the player competes against a solver on the same board.
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+use crate::{Gameboard, gameboard::PlayerCell};
+
+/// Two boards with the same bomb layout, one for the player and one for the
+/// AI.
+pub struct RaceGame {
+    /// The board of the player.
+    pub player: Gameboard,
+    /// The board of the AI.
+    pub ai: Gameboard,
+    /// Picks the cells the AI reveals when it has to guess.
+    rng: StdRng,
+}
+
+impl RaceGame {
+    /// Creates a race on a board built from `seed`, with `first_click`
+    /// already revealed on both sides so that bomb layouts are identical.
+    /// Both boards flag the bombs left once all their safe cells are
+    /// revealed.
+    pub fn new(size: [usize; 2], bombs: usize, seed: u64, first_click: [usize; 2]) -> Self {
+        let mut player = Gameboard::new_seeded(size, bombs, seed);
+        let mut ai = Gameboard::new_seeded(size, bombs, seed);
+        // Bombs enclosed by other bombs cannot be deduced, so both sides win
+        // by revealing all the safe cells.
+        player.auto_flag_on_clear = true;
+        ai.auto_flag_on_clear = true;
+        player.set(first_click, PlayerCell::Revealed);
+        ai.set(first_click, PlayerCell::Revealed);
+        Self {
+            player,
+            ai,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Advances the AI by one move: it applies one deduction of the solver,
+    /// or reveals a random cell if there is none. Returns `false` if the game
+    /// of the AI is over.
+    pub fn ai_tick(&mut self) -> bool {
+        if self.ai.is_over() {
+            return false;
+        }
+        match self.ai.hint() {
+            Some(((x, y), true)) => {
+                self.ai.set([x, y], PlayerCell::Flagged);
+            },
+            Some(((x, y), false)) => {
+                self.ai.set([x, y], PlayerCell::Revealed);
+            },
+            None => {
+                let mut unknown = Vec::new();
+                for y in 0..self.ai.size[1] {
+                    for x in 0..self.ai.size[0] {
+                        if let (PlayerCell::NotDetermined, _) | (PlayerCell::Question, _) | (PlayerCell::Suspected, _) = self.ai.peek(x, y) {
+                            unknown.push([x, y]);
+                        }
+                    }
+                }
+                if unknown.is_empty() {
+                    // Only flags are left, the solver flagged all the bombs.
+                    return false;
+                }
+                let ind = unknown[self.rng.gen_range(0..unknown.len())];
+                self.ai.set(ind, PlayerCell::Revealed);
+            },
+        }
+        true
+    }
+
+    /// Whether the AI won its game.
+    pub fn ai_won(&self) -> bool {
+        self.ai.is_won()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ai_finishes_a_solvable_seed() {
+        for seed in 0..20 {
+            let mut race = RaceGame::new([16, 16], 40, seed, [8, 8]);
+            let mut ticks = 0;
+            while race.ai_tick() {
+                ticks += 1;
+                assert!(ticks <= 16 * 16, "the AI never finishes");
+            }
+        }
+    }
+
+    #[test]
+    fn ai_wins_some_intermediate_races() {
+        let wins = (0..20)
+            .filter(|&seed| {
+                let mut race = RaceGame::new([16, 16], 40, seed, [8, 8]);
+                while race.ai_tick() {}
+                race.ai_won()
+            })
+            .count();
+        assert!(wins > 0);
+    }
+
+    #[test]
+    fn both_boards_share_the_layout() {
+        let race = RaceGame::new([9, 9], 10, 4, [4, 4]);
+        for y in 0..9 {
+            for x in 0..9 {
+                assert_eq!(race.player.peek(x, y), race.ai.peek(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn both_boards_follow_the_same_rules() {
+        let race = RaceGame::new([9, 9], 10, 4, [4, 4]);
+        assert!(race.player.auto_flag_on_clear);
+        assert!(race.ai.auto_flag_on_clear);
+    }
+}
@@ -0,0 +1,295 @@
+//! Replays, to share games and play them again.
+
+use std::{fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Gameboard, RsweeperError, gameboard::{MoveAction, MoveOutcome}};
+
+/// A move of a replay.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ReplayMove {
+    /// The column of the cell.
+    pub x: usize,
+    /// The row of the cell.
+    pub y: usize,
+    /// The move played.
+    pub action: MoveAction,
+}
+
+/// The neighborhood of replays written before `diagonal_cascade` existed.
+fn default_diagonal_cascade() -> bool {
+    true
+}
+
+/// What is needed to play a game again: the board and the moves played on
+/// it, stored as JSON.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Replay {
+    /// The size of the gameboard.
+    pub size: [usize; 2],
+    /// The number of bombs in the game.
+    pub bombs: usize,
+    /// The seed of the bomb placement.
+    pub seed: u64,
+    /// The minimum number of cells uncovered by the first reveal, as it
+    /// changes the bomb placement.
+    pub min_opening: usize,
+    /// Whether the first reveal opens a cascade, as it changes the bomb
+    /// placement. Absent from older replays.
+    #[serde(default)]
+    pub guaranteed_opening: bool,
+    /// Whether diagonal cells are neighbors, as it changes the numbers and
+    /// the reveal cascade. Older replays are diagonal.
+    #[serde(default = "default_diagonal_cascade")]
+    pub diagonal_cascade: bool,
+    /// Whether the player cannot place more flags than there are bombs.
+    /// Absent from older replays.
+    #[serde(default)]
+    pub restrict_flags: bool,
+    /// The number of times revealing a bomb is forgiven when the game
+    /// starts. Absent from older replays.
+    #[serde(default)]
+    pub mercy_moves: usize,
+    /// Whether the bombs left are flagged once all the safe cells are
+    /// revealed. Absent from older replays.
+    #[serde(default)]
+    pub auto_flag_on_clear: bool,
+    /// The maximum number of cells a single move reveals through the
+    /// cascade. Absent from older replays.
+    #[serde(default)]
+    pub max_cascade_per_call: Option<usize>,
+    /// The moves, the first move first.
+    pub moves: Vec<ReplayMove>,
+}
+
+impl Replay {
+    /// Creates the replay of the moves played on a board so far, from its
+    /// event log, leaving the ignored moves out. Playing it ends in the same
+    /// state as the board. Returns `None` if the bomb placement of the board
+    /// is not seeded or the board was changed outside of the moves, see
+    /// `Gameboard::is_fully_logged`, as it cannot be played again.
+    pub fn from_board(gameboard: &Gameboard) -> Option<Self> {
+        if !gameboard.is_fully_logged() {
+            return None;
+        }
+        Some(Self {
+            size: gameboard.size,
+            bombs: gameboard.bombs,
+            seed: gameboard.seed()?,
+            min_opening: gameboard.min_opening,
+            guaranteed_opening: gameboard.guaranteed_opening,
+            diagonal_cascade: gameboard.diagonal_cascade(),
+            restrict_flags: gameboard.restrict_flags,
+            mercy_moves: gameboard.initial_mercy_moves(),
+            auto_flag_on_clear: gameboard.auto_flag_on_clear,
+            max_cascade_per_call: gameboard.max_cascade_per_call,
+            moves: gameboard.events().iter()
+                .filter(|event| matches!(event.outcome, MoveOutcome::Applied { .. }))
+                .map(|event| ReplayMove { x: event.x, y: event.y, action: event.action })
+                .collect(),
+        })
+    }
+
+    /// Parses a replay.
+    pub fn parse(s: &str) -> Result<Self, RsweeperError> {
+        serde_json::from_str(s).map_err(|e| RsweeperError::Parse(e.to_string()))
+    }
+
+    /// Reads the replay file at `path`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, RsweeperError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Writes the replay to `path`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), RsweeperError> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| RsweeperError::Parse(e.to_string()))?;
+        fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Plays the moves on a new board and returns it. Returns an error if the
+    /// board is invalid or a move is out of it.
+    pub fn play(&self) -> Result<Gameboard, RsweeperError> {
+        let mut gameboard = Gameboard::try_new(self.size, self.bombs)?;
+        if !self.diagonal_cascade {
+            gameboard = Gameboard::new_orthogonal(self.size, self.bombs);
+        }
+        gameboard.set_seed(self.seed);
+        gameboard.min_opening = self.min_opening;
+        gameboard.guaranteed_opening = self.guaranteed_opening;
+        gameboard.restrict_flags = self.restrict_flags;
+        gameboard.mercy_moves = self.mercy_moves;
+        gameboard.auto_flag_on_clear = self.auto_flag_on_clear;
+        gameboard.max_cascade_per_call = self.max_cascade_per_call;
+        for m in self.moves.iter() {
+            match m.action {
+                MoveAction::Set(val) => {
+                    gameboard.try_set([m.x, m.y], val)?;
+                },
+                MoveAction::Chord => {
+                    gameboard.try_get_cell(m.x, m.y)?;
+                    gameboard.chord(m.x, m.y);
+                },
+                MoveAction::ContinueCascade => {
+                    let pending = gameboard.cascade_to_continue().to_vec();
+                    gameboard.continue_cascade(&pending);
+                },
+                MoveAction::RevealNeighbors => {
+                    gameboard.try_get_cell(m.x, m.y)?;
+                    gameboard.reveal_neighbors(m.x, m.y);
+                },
+                MoveAction::OpenZeroRegions => gameboard.open_all_zero_regions(),
+                MoveAction::FlagObviousMines => {
+                    gameboard.flag_all_obvious_mines();
+                },
+                MoveAction::ClearQuestions => gameboard.clear_all_questions(),
+                MoveAction::Undo => {
+                    gameboard.undo();
+                },
+                MoveAction::Redo => {
+                    gameboard.redo();
+                },
+                MoveAction::Restart => gameboard.restart_same_layout(),
+                MoveAction::RevealAll => gameboard.reveal_all(),
+            }
+        }
+        Ok(gameboard)
+    }
+}
+
+impl Gameboard {
+    /// Reads the replay file at `path` and returns the board at the end of
+    /// it.
+    pub fn from_replay_file<P: AsRef<Path>>(path: P) -> Result<Gameboard, RsweeperError> {
+        Replay::load(path)?.play()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameboard::{CellContent, PlayerCell};
+
+    #[test]
+    fn replays_keep_the_neighborhood() {
+        let mut board = Gameboard::new_orthogonal([9, 9], 10);
+        board.set_seed(7);
+        board.set([4, 4], PlayerCell::Revealed);
+        let replay = Replay::from_board(&board).unwrap();
+        assert!(!replay.diagonal_cascade);
+
+        let replayed = Replay::parse(&serde_json::to_string(&replay).unwrap()).unwrap().play().unwrap();
+        assert!(!replayed.diagonal_cascade());
+        for y in 0..9 {
+            for x in 0..9 {
+                assert_eq!(replayed.peek(x, y), board.peek(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn older_replays_are_diagonal() {
+        let json = r#"{"size": [9, 9], "bombs": 10, "seed": 7, "min_opening": 0, "moves": []}"#;
+        let replay = Replay::parse(json).unwrap();
+        assert!(replay.diagonal_cascade);
+        assert!(replay.play().unwrap().diagonal_cascade());
+    }
+
+    #[test]
+    fn replay_files_round_trip() {
+        let mut board = Gameboard::new_seeded([9, 9], 10, 5);
+        board.set([4, 4], PlayerCell::Revealed);
+        board.set([0, 0], PlayerCell::Flagged);
+        let path = std::env::temp_dir().join("rsweeper_replay_test.json");
+        let replay = Replay::from_board(&board).unwrap();
+        replay.save(&path).unwrap();
+        assert_eq!(Replay::load(&path).unwrap(), replay);
+        let replayed = Gameboard::from_replay_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(replayed.peek(0, 0), board.peek(0, 0));
+        assert_eq!(replayed.to_ascii(), board.to_ascii());
+        assert!(Replay::from_board(&Gameboard::new([9, 9], 10)).is_none());
+    }
+
+    #[test]
+    fn bulk_actions_are_replayed() {
+        let mut board = Gameboard::new_seeded([16, 16], 40, 3);
+        board.set([8, 8], PlayerCell::Revealed);
+        board.open_all_zero_regions();
+        assert!(board.flag_all_obvious_mines() > 0);
+        let question = (0..16).flat_map(|y| (0..16).map(move |x| [x, y]))
+            .find(|&[x, y]| board.peek(x, y).0 == PlayerCell::NotDetermined)
+            .unwrap();
+        board.set(question, PlayerCell::Question);
+        board.clear_all_questions();
+
+        let replayed = Replay::from_board(&board).unwrap().play().unwrap();
+        for y in 0..16 {
+            for x in 0..16 {
+                assert_eq!(replayed.peek(x, y), board.peek(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn replays_keep_the_guaranteed_opening() {
+        let mut board = Gameboard::new_seeded([9, 9], 30, 2);
+        board.guaranteed_opening = true;
+        board.set([0, 4], PlayerCell::Revealed);
+        let replay = Replay::from_board(&board).unwrap();
+        assert!(replay.guaranteed_opening);
+        assert_eq!(replay.play().unwrap().to_ascii(), board.to_ascii());
+        let json = r#"{"size": [9, 9], "bombs": 10, "seed": 7, "min_opening": 0, "moves": []}"#;
+        assert!(!Replay::parse(json).unwrap().guaranteed_opening);
+    }
+
+    #[test]
+    fn replays_end_in_the_same_state() {
+        let mut board = Gameboard::new_seeded([16, 16], 40, 3);
+        board.min_opening = 20;
+        board.mercy_moves = 1;
+        board.restrict_flags = true;
+        board.max_cascade_per_call = Some(3);
+        board.set([8, 8], PlayerCell::Revealed);
+        let pending = board.cascade_to_continue().to_vec();
+        assert!(!pending.is_empty());
+        board.continue_cascade(&pending);
+        let hidden = (0..256).map(|i| [i % 16, i / 16])
+            .find(|&[x, y]| board.peek(x, y).0 == PlayerCell::NotDetermined)
+            .unwrap();
+        board.set(hidden, PlayerCell::Flagged);
+        assert!(board.undo() && board.redo() && board.undo());
+        board.restart_same_layout();
+
+        // The bomb is forgiven and moved away.
+        let bomb = (0..256).map(|i| [i % 16, i / 16])
+            .find(|&[x, y]| board.peek(x, y).1 == CellContent::Bomb)
+            .unwrap();
+        board.set(bomb, PlayerCell::Revealed);
+        assert_eq!(board.mercy_moves, 0);
+        board.reveal_neighbors(bomb[0], bomb[1]);
+        board.set([0, 0], PlayerCell::Flagged);
+        assert!(board.undo());
+
+        let replay = Replay::from_board(&board).unwrap();
+        assert_eq!(replay.mercy_moves, 1);
+        let replayed = replay.play().unwrap();
+        assert_eq!(replayed.state, board.state);
+        assert_eq!(replayed.flagged, board.flagged);
+        for y in 0..16 {
+            for x in 0..16 {
+                assert_eq!(replayed.peek(x, y), board.peek(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn boards_changed_outside_of_the_moves_are_not_replayed() {
+        let mut board = Gameboard::new_seeded([9, 9], 10, 5);
+        board.set([4, 4], PlayerCell::Revealed);
+        board.apply_diff(&[(0, 0, PlayerCell::Flagged)]);
+        assert!(Replay::from_board(&board).is_none());
+    }
+}
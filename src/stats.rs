@@ -0,0 +1,123 @@
+//! Game statistics.
+
+use std::{collections::HashMap, fs, io, path::Path, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{RsweeperError, gameboard::Difficulty};
+
+/// The statistics of the games played at a given difficulty.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct DifficultyStats {
+    /// The number of games played.
+    pub played: u32,
+    /// The number of games won.
+    pub wins: u32,
+    /// The number of games lost.
+    pub losses: u32,
+    /// The sum of the durations of the games won, in seconds.
+    pub total_win_time: f64,
+}
+
+impl DifficultyStats {
+    /// Gets the fraction of the games played that were won, 0.0 if no game
+    /// was played.
+    pub fn win_rate(&self) -> f64 {
+        if self.played == 0 {
+            0.0
+        } else {
+            self.wins as f64 / self.played as f64
+        }
+    }
+
+    /// Gets the average duration of the games won, if any.
+    pub fn average_win_time(&self) -> Option<Duration> {
+        if self.wins == 0 {
+            None
+        } else {
+            Some(Duration::from_secs_f64(self.total_win_time / self.wins as f64))
+        }
+    }
+}
+
+/// The statistics of the games played, per difficulty.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Stats {
+    by_difficulty: HashMap<Difficulty, DifficultyStats>,
+}
+
+impl Stats {
+    /// Reads the statistics stored at `path` in JSON.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, RsweeperError> {
+        serde_json::from_str(&fs::read_to_string(path)?)
+            .map_err(|e| RsweeperError::Parse(e.to_string()))
+    }
+
+    /// Reads the statistics stored at `path`, starting from empty statistics
+    /// if there is no such file yet.
+    pub fn load_or_default<P: AsRef<Path>>(path: P) -> Result<Self, RsweeperError> {
+        match Self::load(path) {
+            Err(RsweeperError::Io(ref e)) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            res => res,
+        }
+    }
+
+    /// Stores the statistics at `path` in JSON.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), RsweeperError> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| RsweeperError::Parse(e.to_string()))?;
+        fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Records the result of a game.
+    pub fn record_result(&mut self, difficulty: Difficulty, won: bool, time: Duration) {
+        let stats = self.by_difficulty.entry(difficulty).or_default();
+        stats.played += 1;
+        if won {
+            stats.wins += 1;
+            stats.total_win_time += time.as_secs_f64();
+        } else {
+            stats.losses += 1;
+        }
+    }
+
+    /// Gets the statistics of the games played at a given difficulty.
+    pub fn get(&self, difficulty: Difficulty) -> DifficultyStats {
+        self.by_difficulty.get(&difficulty).copied().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_results_per_difficulty() {
+        let mut stats = Stats::default();
+        assert_eq!(stats.get(Difficulty::Beginner).win_rate(), 0.0);
+        assert_eq!(stats.get(Difficulty::Beginner).average_win_time(), None);
+        stats.record_result(Difficulty::Beginner, true, Duration::from_secs(10));
+        stats.record_result(Difficulty::Beginner, true, Duration::from_secs(20));
+        stats.record_result(Difficulty::Beginner, false, Duration::from_secs(5));
+        stats.record_result(Difficulty::Expert, false, Duration::from_secs(5));
+        let beginner = stats.get(Difficulty::Beginner);
+        assert_eq!((beginner.played, beginner.wins, beginner.losses), (3, 2, 1));
+        assert!((beginner.win_rate() - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(beginner.average_win_time(), Some(Duration::from_secs(15)));
+        assert_eq!(stats.get(Difficulty::Expert).win_rate(), 0.0);
+        assert_eq!(stats.get(Difficulty::Intermediate), DifficultyStats::default());
+    }
+
+    #[test]
+    fn stats_files_round_trip() {
+        let path = std::env::temp_dir().join("rsweeper_stats_test.json");
+        let mut stats = Stats::default();
+        stats.record_result(Difficulty::Intermediate, true, Duration::from_secs(42));
+        stats.save(&path).unwrap();
+        let loaded = Stats::load(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap(), stats);
+        assert_eq!(Stats::load_or_default("does/not/exist.json").unwrap(), Stats::default());
+    }
+}
@@ -0,0 +1,121 @@
+//! Color themes, read from JSON files so that they can be shared.
+
+use std::{fs, path::Path};
+
+use graphics::types::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::RsweeperError;
+
+/// The colors of the gameboard view.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Background color.
+    pub background_color: Color,
+    /// Border color.
+    pub border_color: Color,
+    /// Edge color around the whole board.
+    pub board_edge_color: Color,
+    /// Edge color between the 3x3 section.
+    pub section_edge_color: Color,
+    /// Edge color between cells.
+    pub cell_edge_color: Color,
+    /// Selected cell background color.
+    pub selected_cell_background_color: Color,
+    /// Text color.
+    pub text_color: Color,
+    /// Color of the progress bar drawn below the board.
+    pub progress_bar_color: Color,
+    /// Color a cell flashes with when it gets revealed.
+    pub reveal_highlight_color: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            background_color: [0.8, 0.8, 1.0, 1.0],
+            border_color: [0.0, 0.0, 0.2, 1.0],
+            board_edge_color: [0.0, 0.0, 0.2, 1.0],
+            section_edge_color: [0.0, 0.0, 0.2, 1.0],
+            cell_edge_color: [0.0, 0.0, 0.2, 1.0],
+            selected_cell_background_color: [0.9, 0.9, 1.0, 1.0],
+            text_color: [0.0, 0.0, 0.1, 1.0],
+            progress_bar_color: [0.2, 0.7, 0.2, 1.0],
+            reveal_highlight_color: [1.0, 1.0, 0.6, 1.0],
+        }
+    }
+}
+
+impl Theme {
+    /// Parses and validates a theme.
+    pub fn parse(s: &str) -> Result<Self, RsweeperError> {
+        let theme: Self = serde_json::from_str(s)
+            .map_err(|e| RsweeperError::Parse(e.to_string()))?;
+        theme.validate()?;
+        Ok(theme)
+    }
+
+    /// Reads and validates the theme file at `path`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, RsweeperError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Writes the theme to `path`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), RsweeperError> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| RsweeperError::Parse(e.to_string()))?;
+        fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Checks that all the color components are in [0, 1].
+    pub fn validate(&self) -> Result<(), RsweeperError> {
+        let colors = [
+            ("background_color", self.background_color),
+            ("border_color", self.border_color),
+            ("board_edge_color", self.board_edge_color),
+            ("section_edge_color", self.section_edge_color),
+            ("cell_edge_color", self.cell_edge_color),
+            ("selected_cell_background_color", self.selected_cell_background_color),
+            ("text_color", self.text_color),
+            ("progress_bar_color", self.progress_bar_color),
+            ("reveal_highlight_color", self.reveal_highlight_color),
+        ];
+        for (name, color) in colors.iter() {
+            if color.iter().any(|c| !(0.0..=1.0).contains(c)) {
+                return Err(RsweeperError::Parse(format!("{} has components outside of [0, 1]", name)));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_colors_are_defaults() {
+        let theme = Theme::parse(r#"{"text_color": [1.0, 0.0, 0.0, 1.0]}"#).unwrap();
+        assert_eq!(theme.text_color, [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(theme.background_color, Theme::default().background_color);
+    }
+
+    #[test]
+    fn rejects_invalid_themes() {
+        assert!(matches!(Theme::parse(r#"{"text_color": [1.5, 0.0, 0.0, 1.0]}"#), Err(RsweeperError::Parse(_))));
+        assert!(matches!(Theme::parse(r#"{"text_colour": [1.0, 0.0, 0.0, 1.0]}"#), Err(RsweeperError::Parse(_))));
+        assert!(matches!(Theme::parse("not json"), Err(RsweeperError::Parse(_))));
+    }
+
+    #[test]
+    fn theme_files_round_trip() {
+        let path = std::env::temp_dir().join("rsweeper_theme_test.json");
+        let theme = Theme { background_color: [0.1, 0.1, 0.1, 1.0], ..Theme::default() };
+        theme.save(&path).unwrap();
+        let loaded = Theme::load(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap(), theme);
+    }
+}